@@ -0,0 +1,76 @@
+// Compares the bounded distance with the recursive one it replaced.
+// `cargo bench` times both, `cargo test --benches` only runs each pair once.
+
+#[path = "../src/distance.rs"]
+mod distance;
+
+use distance::levensthein_distance;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// The implementation before the bounded one, kept here as the baseline.
+fn recursive_distance(s1: &str, s2: &str, m: usize, n: usize) -> usize {
+    if m == 0 {
+        return n;
+    }
+
+    if n == 0 {
+        return m;
+    }
+
+    let s1_chars: Vec<char> = s1.chars().collect();
+    let s2_chars: Vec<char> = s2.chars().collect();
+
+    if s1_chars[m - 1] == s2_chars[n - 1] {
+        return recursive_distance(s1, s2, m - 1, n - 1);
+    }
+
+    1 + recursive_distance(s1, s2, m, n - 1)
+        .min(recursive_distance(s1, s2, m - 1, n))
+        .min(recursive_distance(s1, s2, m - 1, n - 1))
+}
+
+// A mistyped command against a registered name, like the terminal's suggestions do.
+const PAIRS: [(&str, &str); 6] = [
+    ("pnig", "ping"),
+    ("tmies", "times"),
+    ("barbtu", "barbut"),
+    ("bookmrak", "bookmark"),
+    ("zzzzzzzz", "bookmark"),
+    ("bookmarks", "barbut"),
+];
+
+fn time(iterations: u32, mut f: impl FnMut() -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let iterations = if std::env::args().any(|a| a == "--bench") { 200 } else { 1 };
+
+    println!("{:<22} {:>12} {:>12} {:>12}", "pair", "recursive", "unbounded", "bounded");
+    for (typed, name) in PAIRS {
+        let (m, n) = (typed.chars().count(), name.chars().count());
+        // The terminal allows a third of the typed length, at least one edit.
+        let max = (m / 3).max(1);
+
+        let recursive = time(iterations, || recursive_distance(black_box(typed), black_box(name), m, n));
+        let unbounded = time(iterations, || {
+            levensthein_distance(black_box(typed), black_box(name), usize::MAX).unwrap_or(0)
+        });
+        let bounded = time(iterations, || {
+            levensthein_distance(black_box(typed), black_box(name), max).unwrap_or(usize::MAX)
+        });
+
+        println!(
+            "{:<22} {:>12?} {:>12?} {:>12?}",
+            format!("{typed}/{name}"),
+            recursive,
+            unbounded,
+            bounded
+        );
+    }
+}
@@ -0,0 +1,74 @@
+// Optimal string alignment distance: insertions, deletions, substitutions and swaps of two
+// neighbouring characters all cost one. Gives up with None as soon as it must exceed `max`.
+pub fn levensthein_distance(s1: &str, s2: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = s1.chars().collect();
+    let b: Vec<char> = s2.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    // Only the previous two rows are needed, the one before last for transpositions.
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut row: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        row[0] = i;
+        let mut best = row[0];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+            best = best.min(row[j]);
+        }
+        if best > max {
+            return None;
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut row);
+    }
+
+    Some(prev[b.len()]).filter(|d| *d <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    // Imported per test, benches/levenshtein.rs includes this file without a test harness
+    // and a module-level import would be unused there.
+    #[test]
+    fn measures_edits() {
+        use super::levensthein_distance;
+
+        let cases = [
+            ("", "", 0),
+            ("", "abc", 3),
+            ("abc", "", 3),
+            ("rust", "rust", 0),
+            ("rsut", "rust", 1),
+            ("rust", "rusty", 1),
+            ("rust", "bust", 1),
+            ("ca", "abc", 3),
+            ("kitten", "sitting", 3),
+            ("ünï", "uni", 2),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(levensthein_distance(a, b, usize::MAX), Some(expected), "{a} {b}");
+            assert_eq!(levensthein_distance(b, a, usize::MAX), Some(expected), "{b} {a}");
+        }
+    }
+
+    #[test]
+    fn stops_past_the_limit() {
+        use super::levensthein_distance;
+
+        assert_eq!(levensthein_distance("rust", "rust", 0), Some(0));
+        assert_eq!(levensthein_distance("rsut", "rust", 0), None);
+        assert_eq!(levensthein_distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(levensthein_distance("kitten", "sitting", 2), None);
+        // Decided by the lengths alone.
+        assert_eq!(levensthein_distance("a", "abcdef", 4), None);
+        assert_eq!(levensthein_distance("", "ab", 1), None);
+    }
+}
@@ -0,0 +1,798 @@
+use std::fmt::{self, Write as _};
+
+// A bookmark as it travels through import and export, with unix timestamps.
+#[derive(PartialEq, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub url: String,
+    pub tags: Vec<String>,
+    pub notes: String,
+    pub created: Option<i64>,
+    pub updated: Option<i64>,
+}
+
+pub enum Format {
+    Html,
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Format, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "html" | "htm" => Ok(Format::Html),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format '{name}', expected html, json or csv")),
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Format> {
+        let (_, extension) = path.rsplit_once('.')?;
+        Format::from_name(extension).ok()
+    }
+
+    // For imports without an extension, browsers' exports always start with the doctype.
+    pub fn sniff(text: &str) -> Format {
+        match text.trim_start().chars().next() {
+            Some('<') => Format::Html,
+            Some('[') => Format::Json,
+            _ => Format::Csv,
+        }
+    }
+
+    pub fn read(&self, text: &str) -> Result<Vec<Entry>, String> {
+        match self {
+            Format::Html => Ok(parse_html(text)),
+            Format::Json => parse_json_entries(text),
+            Format::Csv => parse_csv_entries(text),
+        }
+    }
+
+    pub fn write(&self, entries: &[Entry]) -> String {
+        match self {
+            Format::Html => write_html(entries),
+            Format::Json => write_json(entries),
+            Format::Csv => write_csv(entries),
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn decode_html(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => entity.strip_prefix('#').and_then(|d| d.parse().ok()).and_then(char::from_u32),
+                },
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                res.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    res.push_str(rest);
+    res
+}
+
+fn write_html(entries: &[Entry]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file.\n     \
+         It will be read and overwritten.\n     \
+         DO NOT EDIT! -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n\
+         <DL><p>\n",
+    );
+    for entry in entries {
+        let _ = write!(
+            out,
+            "    <DT><A HREF=\"{}\" ADD_DATE=\"{}\" LAST_MODIFIED=\"{}\"",
+            escape_html(&entry.url),
+            entry.created.unwrap_or(0),
+            entry.updated.unwrap_or(0)
+        );
+        if !entry.tags.is_empty() {
+            let _ = write!(out, " TAGS=\"{}\"", escape_html(&entry.tags.join(",")));
+        }
+        let _ = writeln!(out, ">{}</A>", escape_html(&entry.name));
+        if !entry.notes.is_empty() {
+            let _ = writeln!(out, "    <DD>{}", escape_html(&entry.notes));
+        }
+    }
+    out.push_str("</DL><p>\n");
+    out
+}
+
+// Attribute names are matched case-insensitively, browsers write them in upper case.
+fn html_attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let key = format!(" {name}=\"");
+    let start = lower.find(&key)? + key.len();
+    let end = start + tag[start..].find('"')?;
+    Some(decode_html(&tag[start..end]))
+}
+
+// Reads every <A HREF> link, the folders around them are ignored.
+fn parse_html(text: &str) -> Vec<Entry> {
+    let lower = text.to_ascii_lowercase();
+    let mut entries = Vec::new();
+    let mut pos = 0;
+
+    while let Some(found) = lower[pos..].find("<a ") {
+        let start = pos + found;
+        let Some(tag_end) = lower[start..].find('>').map(|i| start + i) else {
+            break;
+        };
+        let close = lower[tag_end..].find("</a>").map_or(text.len(), |i| tag_end + i);
+        let tag = &text[start..tag_end];
+        pos = close;
+
+        let url = html_attribute(tag, "href").unwrap_or_default();
+        // Firefox stores smart folders such as "Most visited" as place: queries.
+        if url.starts_with("place:") {
+            continue;
+        }
+        let date = |name| html_attribute(tag, name).and_then(|d| d.parse::<i64>().ok()).filter(|d| *d > 0);
+        // A description follows the link as <DD>, running up to the next tag.
+        let after = (close + 4).min(text.len());
+        let notes = match lower[after..].trim_start().strip_prefix("<dd>") {
+            Some(rest) => {
+                let start = text.len() - rest.len();
+                let end = text[start..].find('<').map_or(text.len(), |i| start + i);
+                decode_html(text[start..end].trim())
+            }
+            None => String::new(),
+        };
+
+        entries.push(Entry {
+            name: decode_html(text[tag_end + 1..close].trim()),
+            url,
+            tags: html_attribute(tag, "tags").map_or(Vec::new(), |t| split_tags(&t, ',')),
+            notes,
+            created: date("add_date"),
+            updated: date("last_modified"),
+        });
+    }
+
+    entries
+}
+
+fn split_tags(text: &str, separator: char) -> Vec<String> {
+    text.split(separator)
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn escape_json(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+// One bookmark per line keeps diffs of exported files readable.
+fn write_json(entries: &[Entry]) -> String {
+    let mut out = String::from("[\n");
+    for (i, entry) in entries.iter().enumerate() {
+        let number = |n: Option<i64>| n.map_or(Json::Null, |n| Json::Number(n as f64));
+        let item = Json::Object(vec![
+            ("name".to_string(), Json::String(entry.name.clone())),
+            ("url".to_string(), Json::String(entry.url.clone())),
+            (
+                "tags".to_string(),
+                Json::Array(entry.tags.iter().cloned().map(Json::String).collect()),
+            ),
+            ("notes".to_string(), Json::String(entry.notes.clone())),
+            ("created".to_string(), number(entry.created)),
+            ("updated".to_string(), number(entry.updated)),
+        ]);
+        let _ = write!(out, "  {item}");
+        out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("]\n");
+    out
+}
+
+#[derive(PartialEq, Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(n) => Some(*n as i64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => escape_json(f, s),
+            Json::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    escape_json(f, key)?;
+                    write!(f, ": {value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl JsonParser<'_> {
+    fn error(&mut self, what: &str) -> String {
+        match self.chars.peek() {
+            Some((i, c)) => format!("expected {what} at byte {i}, found '{c}'"),
+            None => format!("expected {what} at the end of the file"),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next_if(|(_, c)| *c == expected) {
+            Some(_) => Ok(()),
+            None => Err(self.error(&format!("'{expected}'"))),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.chars.next_if(|(_, c)| *c == expected).is_none() {
+                return Err(self.error(word));
+            }
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.peek().map(|(_, c)| *c) {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(Json::Array(items)),
+                _ => return Err(self.error("',' or ']'")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.chars.next().and_then(|(_, c)| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("four hex digits")),
+            }
+        }
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.chars.next_if(|(_, c)| *c == '"').is_none() {
+            return Err(self.error("a string"));
+        }
+        let mut res = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(res),
+                Some((_, '\\')) => match self.chars.next().map(|(_, c)| c) {
+                    Some('n') => res.push('\n'),
+                    Some('t') => res.push('\t'),
+                    Some('r') => res.push('\r'),
+                    Some('b') => res.push('\u{8}'),
+                    Some('f') => res.push('\u{c}'),
+                    Some('u') => {
+                        let code = self.hex4()?;
+                        // Characters outside the BMP come as a surrogate pair. The low half
+                        // needs two characters of lookahead, any other escape is left alone.
+                        let mut ahead = self.chars.clone();
+                        let paired = (0xd800..0xdc00).contains(&code)
+                            && matches!((ahead.next(), ahead.next()), (Some((_, '\\')), Some((_, 'u'))));
+                        if !paired {
+                            res.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                            continue;
+                        }
+                        self.chars.nth(1);
+                        let low = self.hex4()?;
+                        if (0xdc00..0xe000).contains(&low) {
+                            let pair = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            res.push(char::from_u32(pair).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        } else {
+                            // A lone high half, followed by whatever the second escape was.
+                            res.push(char::REPLACEMENT_CHARACTER);
+                            res.push(char::from_u32(low).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                    }
+                    Some(c) => res.push(c),
+                    None => return Err(self.error("an escape")),
+                },
+                Some((_, c)) => res.push(c),
+                None => return Err(self.error("'\"'")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let mut text = String::new();
+        while let Some((_, c)) = self.chars.next_if(|(_, c)| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+            text.push(c);
+        }
+        match text.parse() {
+            Ok(n) => Ok(Json::Number(n)),
+            Err(_) => Err(format!("'{text}' is not a number")),
+        }
+    }
+}
+
+fn parse_json(text: &str) -> Result<Json, String> {
+    let mut parser = JsonParser {
+        chars: text.char_indices().peekable(),
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err(parser.error("the end of the file"));
+    }
+    Ok(value)
+}
+
+// Takes the array written by `bk export --format json`.
+fn parse_json_entries(text: &str) -> Result<Vec<Entry>, String> {
+    let invalid = |e: String| format!("invalid JSON: {e}");
+    let Json::Array(items) = parse_json(text).map_err(invalid)? else {
+        return Err(invalid("expected an array of bookmarks".to_string()));
+    };
+
+    let entries = items
+        .iter()
+        .map(|item| {
+            let text = |key| item.get(key).and_then(Json::as_str).unwrap_or_default().to_string();
+            let tags = match item.get("tags") {
+                Some(Json::Array(tags)) => tags.iter().filter_map(Json::as_str).map(str::to_string).collect(),
+                Some(Json::String(tags)) => split_tags(tags, ','),
+                _ => Vec::new(),
+            };
+            Entry {
+                name: text("name"),
+                url: text("url"),
+                tags,
+                notes: text("notes"),
+                created: item.get("created").and_then(Json::as_i64),
+                updated: item.get("updated").and_then(Json::as_i64),
+            }
+        })
+        .collect();
+    Ok(entries)
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Tags are space separated within their column, the way most bookmarking sites export them.
+fn write_csv(entries: &[Entry]) -> String {
+    let mut out = String::from("name,url,tags,notes,created,updated\n");
+    for entry in entries {
+        let number = |n: Option<i64>| n.map_or(String::new(), |n| n.to_string());
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            escape_csv(&entry.name),
+            escape_csv(&entry.url),
+            escape_csv(&entry.tags.join(" ")),
+            escape_csv(&entry.notes),
+            number(entry.created),
+            number(entry.updated)
+        );
+    }
+    out
+}
+
+// RFC 4180, quoted fields may hold commas, doubled quotes and line breaks.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+// Columns are found by their header, so files with only some of them (or extra ones) still load.
+fn parse_csv_entries(text: &str) -> Result<Vec<Entry>, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+    let Some(url) = column(&["url", "href"]) else {
+        return Err("the CSV header has no url column".to_string());
+    };
+    let name = column(&["name", "title"]);
+    let tags = column(&["tags"]);
+    let notes = column(&["notes", "description"]);
+    let created = column(&["created"]);
+    let updated = column(&["updated"]);
+
+    let entries = rows
+        .filter(|row| row.iter().any(|f| !f.trim().is_empty()))
+        .map(|row| {
+            let field = |i: Option<usize>| i.and_then(|i| row.get(i)).map_or("", |f| f.trim());
+            Entry {
+                name: field(name).to_string(),
+                url: field(Some(url)).to_string(),
+                tags: split_tags(field(tags), ' '),
+                notes: field(notes).to_string(),
+                created: field(created).parse().ok(),
+                updated: field(updated).parse().ok(),
+            }
+        })
+        .collect();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, url: &str, tags: &[&str], notes: &str, created: Option<i64>, updated: Option<i64>) -> Entry {
+        Entry {
+            name: name.to_string(),
+            url: url.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            notes: notes.to_string(),
+            created,
+            updated,
+        }
+    }
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            entry("Rust", "https://www.rust-lang.org/", &["lang", "rust"], "", Some(1700000000), Some(1700000100)),
+            entry(
+                r#"Quotes "and", commas & <brackets>"#,
+                "https://example.com/search?a=1&b=\"2\"",
+                &[],
+                "two\nlines, \"quoted\" & <b>",
+                Some(1),
+                None,
+            ),
+            entry("Ünïcødé 😀", "https://example.com/ü", &["ü"], "tab\there \\ backslash", None, None),
+        ]
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        for format in [Format::Html, Format::Json, Format::Csv] {
+            let text = format.write(&entries());
+            assert_eq!(format.read(&text), Ok(entries()), "{text}");
+            assert!(matches!(
+                (Format::sniff(&text), &format),
+                (Format::Html, Format::Html) | (Format::Json, Format::Json) | (Format::Csv, Format::Csv)
+            ));
+        }
+        assert_eq!(Format::Json.read(&Format::Json.write(&[])), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn reads_a_firefox_export() {
+        let text = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<meta http-equiv="Content-Security-Policy"
+      content="default-src 'self'; script-src 'none'; img-src data: *; object-src 'none'"></meta>
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks Menu</H1>
+
+<DL><p>
+    <DT><H3 ADD_DATE="1690000000" LAST_MODIFIED="1700000000" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks Toolbar</H3>
+    <DD>Add bookmarks to this folder to see them displayed on the Bookmarks Toolbar
+    <DL><p>
+        <DT><A HREF="place:sort=8&amp;maxResults=10" ADD_DATE="1690000001" LAST_MODIFIED="1690000001">Recently Tagged</A>
+        <DT><A HREF="https://doc.rust-lang.org/std/" ADD_DATE="1690000002" LAST_MODIFIED="1690000003" ICON_URI="https://doc.rust-lang.org/favicon.ico" ICON="data:image/png;base64,iVBORw0KGgo=" TAGS="docs,rust">std - Rust</A>
+        <DD>The standard library &amp; its &lt;modules&gt;
+        <DT><H3 ADD_DATE="1690000004" LAST_MODIFIED="1690000005">Reading</H3>
+        <DL><p>
+            <DT><A HREF="https://example.com/it&#39;s" ADD_DATE="1690000006" LAST_MODIFIED="0">It&#39;s &#x201C;quoted&#x201D;</A>
+        </DL><p>
+    </DL><p>
+    <HR>
+    <DT><A HREF="https://www.mozilla.org/" ADD_DATE="1690000007">Mozilla</A>
+</DL>
+"#;
+        assert_eq!(
+            Format::sniff(text).read(text),
+            Ok(vec![
+                entry(
+                    "std - Rust",
+                    "https://doc.rust-lang.org/std/",
+                    &["docs", "rust"],
+                    "The standard library & its <modules>",
+                    Some(1690000002),
+                    Some(1690000003),
+                ),
+                entry("It's \u{201c}quoted\u{201d}", "https://example.com/it's", &[], "", Some(1690000006), None),
+                entry("Mozilla", "https://www.mozilla.org/", &[], "", Some(1690000007), None),
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_a_chrome_export() {
+        let text = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000" LAST_MODIFIED="1700000500" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://www.rust-lang.org/" ADD_DATE="1700000001" ICON="data:image/png;base64,iVBORw0KGgo=">Rust Programming Language</A>
+        <DT><H3 ADD_DATE="1700000002" LAST_MODIFIED="0">Docs</H3>
+        <DL><p>
+            <DT><A HREF="https://docs.rs/" ADD_DATE="1700000003">Docs.rs</A>
+        </DL><p>
+    </DL><p>
+    <DT><H3 ADD_DATE="1700000004" LAST_MODIFIED="0">Other bookmarks</H3>
+    <DL><p>
+    </DL><p>
+</DL><p>
+"#;
+        assert_eq!(
+            Format::Html.read(text),
+            Ok(vec![
+                entry("Rust Programming Language", "https://www.rust-lang.org/", &[], "", Some(1700000001), None),
+                entry("Docs.rs", "https://docs.rs/", &[], "", Some(1700000003), None),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_json_values() {
+        assert_eq!(
+            parse_json(r#" {"a": [true, false, null, -1.5e2, "x"], "b": {}} "#),
+            Ok(Json::Object(vec![
+                (
+                    "a".to_string(),
+                    Json::Array(vec![
+                        Json::Bool(true),
+                        Json::Bool(false),
+                        Json::Null,
+                        Json::Number(-150.0),
+                        Json::String("x".to_string()),
+                    ])
+                ),
+                ("b".to_string(), Json::Object(Vec::new())),
+            ]))
+        );
+
+        let value = parse_json(r#"[true, {"k": "v\"\n"}, [], 12]"#).unwrap();
+        assert_eq!(value.to_string(), r#"[true, {"k": "v\"\n"}, [], 12]"#);
+
+        for bad in ["", "[1,]", "tru", "{\"a\" 1}", "\"open", "[1] 2", "\"\\u12\""] {
+            assert!(parse_json(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn decodes_json_escapes() {
+        let string = |text: &str| match parse_json(text) {
+            Ok(Json::String(s)) => s,
+            other => panic!("{text}: {other:?}"),
+        };
+        assert_eq!(string(r#""\u00e9\/\b\f""#), "é/\u{8}\u{c}");
+        assert_eq!(string(r#""\ud83d\ude00""#), "😀");
+        // A high surrogate without its low half must not swallow the next escape.
+        assert_eq!(string(r#""\ud83d\n""#), "\u{fffd}\n");
+        assert_eq!(string(r#""\ud83d\"x""#), "\u{fffd}\"x");
+        assert_eq!(string(r#""\ud83dx""#), "\u{fffd}x");
+        assert_eq!(string(r#""\ud83d\u0041""#), "\u{fffd}A");
+        assert_eq!(string(r#""\ude00""#), "\u{fffd}");
+    }
+
+    #[test]
+    fn reads_json_with_other_fields() {
+        let text = r#"[{"name": "a", "url": "https://a.example", "private": true, "tags": "x, y", "created": 5},
+                      {"url": "https://b.example", "archived": false, "tags": null}]"#;
+        assert_eq!(
+            Format::Json.read(text),
+            Ok(vec![
+                entry("a", "https://a.example", &["x", "y"], "", Some(5), None),
+                entry("", "https://b.example", &[], "", None, None),
+            ])
+        );
+        assert!(Format::Json.read(r#"{"url": "https://a.example"}"#).is_err());
+    }
+
+    #[test]
+    fn reads_csv_from_elsewhere() {
+        let text = "Title,URL,Description,Extra\r\n\
+                    \"Multi, line\",https://a.example,\"first\nsecond \"\"quoted\"\"\",x\r\n\
+                    ,,,\r\n\
+                    Short,https://b.example\r\n";
+        assert_eq!(
+            Format::Csv.read(text),
+            Ok(vec![
+                entry("Multi, line", "https://a.example", &[], "first\nsecond \"quoted\"", None, None),
+                entry("Short", "https://b.example", &[], "", None, None),
+            ])
+        );
+        assert!(Format::Csv.read("name,notes\na,b\n").is_err());
+    }
+
+    #[test]
+    fn picks_formats() {
+        assert!(matches!(Format::from_path("out/bookmarks.HTM"), Some(Format::Html)));
+        assert!(matches!(Format::from_path("bookmarks.json"), Some(Format::Json)));
+        assert!(Format::from_path("bookmarks").is_none());
+        assert!(Format::from_name("xml").is_err());
+        assert!(matches!(Format::sniff("\n  [{}]"), Format::Json));
+    }
+}
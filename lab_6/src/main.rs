@@ -1,14 +1,252 @@
+mod distance;
+mod formats;
+
+use distance::levensthein_distance;
+use formats::{Entry, Format};
 use rand::Rng;
-use rusqlite::Connection;
+use rusqlite::{Connection, Transaction};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+const HISTORY_FILE: &str = ".lab_6_history";
+const BOOKMARKS_DB: &str = "bookmarks.db";
+const SEARCH_LIMIT: usize = 20;
+const MAX_SUGGESTIONS: usize = 3;
+
+// Applied in order inside one transaction each, `user_version` records how many have run.
+const MIGRATIONS: &[fn(&Transaction) -> rusqlite::Result<()>] = &[create_bookmarks, index_bookmarks, index_text];
+
+enum CommandError {
+    Usage(String),
+    Failed(String),
+    Database(rusqlite::Error),
+    Io(io::Error),
+}
+
+// What a command's text went to is up to the terminal, this only says whether to go on.
+enum Output {
+    Nothing,
+    Stop(i32),
+}
+
+type CommandResult = Result<Output, CommandError>;
+
+#[derive(PartialEq, Clone, Copy)]
+enum Arity {
+    Required,
+    Optional,
+    // Any number of trailing arguments, including none.
+    Rest,
+    // `--name` anywhere after the action, on its own or followed by a value.
+    Flag,
+    Value(&'static str),
+}
+
+struct ArgSpec {
+    name: &'static str,
+    arity: Arity,
+}
+
+// A sub-command such as `bk add`, with its own arguments.
+struct Action {
+    name: &'static str,
+    args: &'static [ArgSpec],
+    description: &'static str,
+}
 
 trait MyCommand {
     fn get_name(&self) -> &str {
         "Default name"
     }
-    fn exec(&mut self, args: &[&str]);
+    fn description(&self) -> &str {
+        ""
+    }
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+    fn args(&self) -> &[ArgSpec] {
+        &[]
+    }
+    fn actions(&self) -> &[Action] {
+        &[]
+    }
+    fn exec(&mut self, args: &[&str], out: &mut dyn Write) -> CommandResult;
+}
+
+const fn required(name: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        arity: Arity::Required,
+    }
+}
+
+const fn optional(name: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        arity: Arity::Optional,
+    }
+}
+
+const fn rest(name: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        arity: Arity::Rest,
+    }
+}
+
+const fn flag(name: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        arity: Arity::Flag,
+    }
+}
+
+const fn value(name: &'static str, value: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        arity: Arity::Value(value),
+    }
+}
+
+fn syntax(prefix: &str, args: &[ArgSpec]) -> String {
+    let mut line = prefix.to_string();
+    for arg in args {
+        match arg.arity {
+            Arity::Required => line.push_str(&format!(" <{}>", arg.name)),
+            Arity::Optional => line.push_str(&format!(" [{}]", arg.name)),
+            Arity::Rest => line.push_str(&format!(" [{}...]", arg.name)),
+            Arity::Flag => line.push_str(&format!(" [--{}]", arg.name)),
+            Arity::Value(value) => line.push_str(&format!(" [--{} <{}>]", arg.name, value)),
+        }
+    }
+    line
+}
+
+type Flags<'a> = HashMap<&'static str, &'a str>;
+
+// Separates the declared flags from the positional arguments, a flag without a value maps to "".
+fn split_flags<'a>(args: &[&'a str], spec: &[ArgSpec]) -> Result<(Vec<&'a str>, Flags<'a>), String> {
+    let mut positional = Vec::new();
+    let mut flags = HashMap::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let declared = arg
+            .strip_prefix("--")
+            .and_then(|name| spec.iter().find(|a| a.name == name && matches!(a.arity, Arity::Flag | Arity::Value(_))));
+        match declared.map(|a| (a.name, a.arity)) {
+            Some((name, Arity::Value(_))) => match args.next() {
+                Some(value) => {
+                    flags.insert(name, *value);
+                }
+                None => return Err(format!("--{name} needs a value")),
+            },
+            Some((name, _)) => {
+                flags.insert(name, "");
+            }
+            None => positional.push(*arg),
+        }
+    }
+
+    Ok((positional, flags))
+}
+
+// One line per action for commands that have them, otherwise a single line.
+fn usage(command: &dyn MyCommand) -> Vec<String> {
+    let name = command.get_name();
+    if command.actions().is_empty() {
+        return vec![syntax(name, command.args())];
+    }
+
+    command
+        .actions()
+        .iter()
+        .map(|action| syntax(&format!("{name} {}", action.name), action.args))
+        .collect()
+}
+
+fn check_count(args: &[&str], spec: &[ArgSpec], usage: String) -> Result<(), CommandError> {
+    let (args, _) = split_flags(args, spec).map_err(|_| CommandError::Usage(usage.clone()))?;
+    let min = spec.iter().filter(|a| a.arity == Arity::Required).count();
+    let max = spec
+        .iter()
+        .filter(|a| matches!(a.arity, Arity::Required | Arity::Optional))
+        .count();
+    let unbounded = spec.iter().any(|a| a.arity == Arity::Rest);
+
+    if args.len() < min || (!unbounded && args.len() > max) {
+        return Err(CommandError::Usage(usage));
+    }
+    Ok(())
+}
+
+// Checked before `exec`, so commands can index the arguments they declared as required.
+// Commands that declare no arguments at all, like `ping`, ignore whatever they are given.
+fn check_args(command: &dyn MyCommand, args: &[&str]) -> Result<(), CommandError> {
+    let actions = command.actions();
+    if actions.is_empty() && command.args().is_empty() {
+        return Ok(());
+    }
+    if actions.is_empty() {
+        return check_count(args, command.args(), syntax(command.get_name(), command.args()));
+    }
+
+    let action = args
+        .first()
+        .and_then(|first| actions.iter().find(|a| a.name == *first));
+    match action {
+        Some(action) => check_count(
+            &args[1..],
+            action.args,
+            syntax(&format!("{} {}", command.get_name(), action.name), action.args),
+        ),
+        None => Err(CommandError::Usage(usage(command).join(" | "))),
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::Usage(usage) => write!(f, "usage: {usage}"),
+            CommandError::Failed(message) => write!(f, "{message}"),
+            CommandError::Database(e) => write!(f, "database error: {e}"),
+            CommandError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(e: io::Error) -> Self {
+        CommandError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for CommandError {
+    fn from(e: rusqlite::Error) -> Self {
+        CommandError::Database(e)
+    }
+}
+
+// What running one line amounted to, the Terminal decides what to do next from it.
+#[derive(PartialEq, Clone, Copy)]
+enum Status {
+    Success,
+    Failure,
+    Stop(i32),
 }
 
 fn barbut_roll_generator() -> (i32, i32) {
@@ -16,25 +254,371 @@ fn barbut_roll_generator() -> (i32, i32) {
     (rng.gen_range(1..=6), rng.gen_range(1..=6))
 }
 
-fn levensthein_distance(s1: &str, s2: &str, m: usize, n: usize) -> usize {
-    if m == 0 {
-        return n;
+#[derive(Debug)]
+struct ParseError {
+    column: usize,
+    message: String,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum Operator {
+    Pipe,
+    Redirect,
+    Append,
+    And,
+    Or,
+    Then,
+}
+
+// Pieces of a word as typed. Variables are only looked up when their step runs,
+// so `set X 1; count $X` already sees the new value.
+enum Part {
+    Text(String),
+    // The name and the column of its `$`.
+    Var(String, usize),
+}
+
+struct Word(Vec<Part>);
+
+enum Token {
+    Word(Word),
+    // Operators keep their column so a misplaced one can be pointed at.
+    Op(Operator, usize),
+}
+
+struct Stage {
+    words: Vec<Word>,
+    // The file and whether to append to it.
+    redirect: Option<(Word, bool)>,
+}
+
+// A stage with its variables filled in.
+struct Invocation {
+    words: Vec<String>,
+    redirect: Option<(String, bool)>,
+}
+
+// A pipeline and the operator that decides whether it runs after the previous one.
+struct Step {
+    after: Operator,
+    stages: Vec<Stage>,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// Reads `$NAME` or `${NAME}` starting right after the `$`. Returns None when no
+// name follows, so a lone `$` stays literal.
+fn variable_name(chars: &[char], i: &mut usize, column: usize) -> Result<Option<String>, ParseError> {
+    let braced = chars.get(*i) == Some(&'{');
+    let start = if braced { *i + 1 } else { *i };
+    let mut end = start;
+    while end < chars.len() && is_name_char(chars[end]) {
+        end += 1;
     }
 
-    if n == 0 {
-        return m;
+    if end == start || chars[start].is_ascii_digit() {
+        if braced {
+            return Err(ParseError {
+                column,
+                message: "bad variable name".to_string(),
+            });
+        }
+        return Ok(None);
+    }
+    if braced && chars.get(end) != Some(&'}') {
+        return Err(ParseError {
+            column,
+            message: "missing '}'".to_string(),
+        });
     }
 
-    let s1_chars: Vec<char> = s1.chars().collect();
-    let s2_chars: Vec<char> = s2.chars().collect();
+    *i = if braced { end + 1 } else { end };
+    Ok(Some(chars[start..end].iter().collect()))
+}
 
-    if s1_chars[m - 1] == s2_chars[n - 1] {
-        return levensthein_distance(s1, s2, m - 1, n - 1);
+impl Word {
+    fn expand(&self, vars: &HashMap<String, String>) -> Result<String, ParseError> {
+        let mut word = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => word.push_str(text),
+                // Variables from `set` shadow the environment.
+                Part::Var(name, column) => match vars.get(name).cloned().or_else(|| std::env::var(name).ok()) {
+                    Some(value) => word.push_str(&value),
+                    None => {
+                        return Err(ParseError {
+                            column: *column,
+                            message: format!("undefined variable '{name}'"),
+                        });
+                    }
+                },
+            }
+        }
+        Ok(word)
     }
+}
 
-    1 + levensthein_distance(s1, s2, m, n - 1)
-        .min(levensthein_distance(s1, s2, m - 1, n))
-        .min(levensthein_distance(s1, s2, m - 1, n - 1))
+impl Stage {
+    fn expand(&self, vars: &HashMap<String, String>) -> Result<Invocation, ParseError> {
+        Ok(Invocation {
+            words: self.words.iter().map(|w| w.expand(vars)).collect::<Result<_, _>>()?,
+            redirect: match &self.redirect {
+                Some((path, append)) => Some((path.expand(vars)?, *append)),
+                None => None,
+            },
+        })
+    }
+}
+
+// Ends the literal text collected so far, a variable or the end of the word follows.
+fn end_text(current: &mut String, parts: &mut Vec<Part>) {
+    if !current.is_empty() {
+        parts.push(Part::Text(std::mem::take(current)));
+    }
+}
+
+fn end_word(current: &mut String, parts: &mut Vec<Part>) -> Token {
+    end_text(current, parts);
+    Token::Word(Word(std::mem::take(parts)))
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Pipe => "|",
+            Operator::Redirect => ">",
+            Operator::Append => ">>",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Then => ";",
+        }
+    }
+}
+
+// Splits a line like a shell would: quotes group words, backslash escapes the
+// next character, `#` starts a comment and `$NAME` is a variable outside single quotes.
+// Unquoted `|`, `||`, `>`, `>>`, `&&` and `;` are operators even without spaces around them.
+fn tokenize(line: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut parts = Vec::new();
+    let mut in_token = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+
+        match c {
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(end_word(&mut current, &mut parts));
+                    in_token = false;
+                }
+                i += 1;
+            }
+            '|' | '>' | ';' | '&' if c != '&' || chars.get(i + 1) == Some(&'&') => {
+                if in_token {
+                    tokens.push(end_word(&mut current, &mut parts));
+                    in_token = false;
+                }
+                let doubled = c != ';' && chars.get(i + 1) == Some(&c);
+                let op = match (c, doubled) {
+                    ('|', false) => Operator::Pipe,
+                    ('|', true) => Operator::Or,
+                    ('>', false) => Operator::Redirect,
+                    ('>', true) => Operator::Append,
+                    ('&', _) => Operator::And,
+                    _ => Operator::Then,
+                };
+                tokens.push(Token::Op(op, column));
+                i += if doubled { 2 } else { 1 };
+            }
+            '#' if !in_token => break,
+            '\\' => {
+                match chars.get(i + 1) {
+                    Some(next) => current.push(*next),
+                    None => {
+                        return Err(ParseError {
+                            column,
+                            message: "nothing to escape at end of line".to_string(),
+                        });
+                    }
+                }
+                in_token = true;
+                i += 2;
+            }
+            '\'' => {
+                let close = match chars[i + 1..].iter().position(|c| *c == '\'') {
+                    Some(offset) => i + 1 + offset,
+                    None => {
+                        return Err(ParseError {
+                            column,
+                            message: "unterminated single quote".to_string(),
+                        });
+                    }
+                };
+                current.extend(&chars[i + 1..close]);
+                in_token = true;
+                i = close + 1;
+            }
+            '"' => {
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(ParseError {
+                                column,
+                                message: "unterminated double quote".to_string(),
+                            });
+                        }
+                        Some('"') => break,
+                        // Inside double quotes only these keep their special meaning.
+                        Some('\\') if matches!(chars.get(i + 1), Some('"' | '\\' | '$')) => {
+                            current.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some('$') => {
+                            let dollar = i + 1;
+                            i += 1;
+                            match variable_name(&chars, &mut i, dollar)? {
+                                Some(name) => {
+                                    end_text(&mut current, &mut parts);
+                                    parts.push(Part::Var(name, dollar));
+                                }
+                                None => current.push('$'),
+                            }
+                        }
+                        Some(c) => {
+                            current.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                in_token = true;
+                i += 1;
+            }
+            '$' => {
+                i += 1;
+                match variable_name(&chars, &mut i, column)? {
+                    Some(name) => {
+                        end_text(&mut current, &mut parts);
+                        parts.push(Part::Var(name, column));
+                    }
+                    None => current.push('$'),
+                }
+                in_token = true;
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+                i += 1;
+            }
+        }
+    }
+
+    if in_token {
+        tokens.push(end_word(&mut current, &mut parts));
+    }
+
+    Ok(tokens)
+}
+
+// Groups the tokens into pipelines joined by `&&`, `||` and `;`. A trailing `;` is allowed.
+fn parse_steps(tokens: Vec<Token>) -> Result<Vec<Step>, ParseError> {
+    let mut steps = Vec::new();
+    let mut after = Operator::Then;
+    let mut stages = Vec::new();
+    let mut stage = Stage {
+        words: Vec::new(),
+        redirect: None,
+    };
+    let mut tokens = tokens.into_iter().peekable();
+    let mut last_column = 1;
+
+    let missing = |op: Operator, column| ParseError {
+        column,
+        message: format!("missing command before '{}'", op.symbol()),
+    };
+
+    while let Some(token) = tokens.next() {
+        let (op, column) = match token {
+            Token::Word(word) => {
+                stage.words.push(word);
+                continue;
+            }
+            Token::Op(op, column) => (op, column),
+        };
+        last_column = column;
+
+        match op {
+            Operator::Redirect | Operator::Append => match tokens.next() {
+                Some(Token::Word(path)) => stage.redirect = Some((path, op == Operator::Append)),
+                _ => {
+                    return Err(ParseError {
+                        column,
+                        message: format!("missing file after '{}'", op.symbol()),
+                    });
+                }
+            },
+            Operator::Pipe => {
+                if stage.words.is_empty() {
+                    return Err(missing(op, column));
+                }
+                stages.push(std::mem::replace(
+                    &mut stage,
+                    Stage {
+                        words: Vec::new(),
+                        redirect: None,
+                    },
+                ));
+            }
+            Operator::And | Operator::Or | Operator::Then => {
+                if stage.words.is_empty() {
+                    // `a ;` and a line that is only `;` are harmless, a dangling && or || is not.
+                    if op == Operator::Then && stages.is_empty() && tokens.peek().is_none() {
+                        continue;
+                    }
+                    return Err(missing(op, column));
+                }
+                stages.push(std::mem::replace(
+                    &mut stage,
+                    Stage {
+                        words: Vec::new(),
+                        redirect: None,
+                    },
+                ));
+                steps.push(Step {
+                    after,
+                    stages: std::mem::take(&mut stages),
+                });
+                after = op;
+            }
+        }
+    }
+
+    if stage.words.is_empty() {
+        if !stages.is_empty() || after != Operator::Then {
+            return Err(ParseError {
+                column: last_column,
+                message: "missing command at the end of the line".to_string(),
+            });
+        }
+        if stage.redirect.is_some() {
+            return Err(ParseError {
+                column: last_column,
+                message: "nothing to redirect".to_string(),
+            });
+        }
+    } else {
+        stages.push(stage);
+        steps.push(Step { after, stages });
+    }
+
+    Ok(steps)
 }
 
 struct PingCommand {}
@@ -44,21 +628,50 @@ struct TimesCommand {
 }
 struct BarbutCommand {}
 struct StopCommand {}
-struct BookmarkCommand {}
+struct SetCommand {
+    vars: Rc<RefCell<HashMap<String, String>>>,
+}
+struct HelpTopic {
+    name: String,
+    aliases: Vec<String>,
+    description: String,
+    usage: Vec<String>,
+    actions: Vec<(String, String)>,
+}
+struct HelpCommand {
+    topics: Rc<RefCell<Vec<HelpTopic>>>,
+}
+struct BookmarkCommand {
+    // Opened on first use so the terminal still starts when the database can't be.
+    conn: Option<Connection>,
+}
 struct Bookmark {
     name: String,
     url: String,
+    tags: String,
+    notes: String,
+    created: String,
+    updated: String,
 }
 struct Terminal {
     commands: Vec<Box<dyn MyCommand>>,
+    vars: Rc<RefCell<HashMap<String, String>>>,
+    topics: Rc<RefCell<Vec<HelpTopic>>>,
+}
+struct CommandCompleter {
+    names: Vec<String>,
 }
 
 impl MyCommand for PingCommand {
     fn get_name(&self) -> &str {
         "ping"
     }
-    fn exec(&mut self, _args: &[&str]) {
-        println!("pong");
+    fn description(&self) -> &str {
+        "Answer with pong"
+    }
+    fn exec(&mut self, _args: &[&str], out: &mut dyn Write) -> CommandResult {
+        writeln!(out, "pong")?;
+        Ok(Output::Nothing)
     }
 }
 
@@ -66,13 +679,15 @@ impl MyCommand for CountCommand {
     fn get_name(&self) -> &str {
         "count"
     }
-    fn exec(&mut self, args: &[&str]) {
-        let mut i = 0u8;
-        for _ in args {
-            i += 1;
-        }
-
-        println!("counted {i} args");
+    fn description(&self) -> &str {
+        "Count the arguments it was given"
+    }
+    fn args(&self) -> &[ArgSpec] {
+        const { &[rest("args")] }
+    }
+    fn exec(&mut self, args: &[&str], out: &mut dyn Write) -> CommandResult {
+        writeln!(out, "counted {} args", args.len())?;
+        Ok(Output::Nothing)
     }
 }
 
@@ -80,9 +695,13 @@ impl MyCommand for TimesCommand {
     fn get_name(&self) -> &str {
         "times"
     }
-    fn exec(&mut self, _args: &[&str]) {
+    fn description(&self) -> &str {
+        "Tell how many times it has been called"
+    }
+    fn exec(&mut self, _args: &[&str], out: &mut dyn Write) -> CommandResult {
         self.count += 1;
-        println!("Times called {} times.", self.count.clone());
+        writeln!(out, "Times called {} times.", self.count)?;
+        Ok(Output::Nothing)
     }
 }
 
@@ -90,21 +709,31 @@ impl MyCommand for BarbutCommand {
     fn get_name(&self) -> &str {
         "barbut"
     }
-    fn exec(&mut self, _args: &[&str]) {
+    fn description(&self) -> &str {
+        "Roll two dice against the computer"
+    }
+    fn aliases(&self) -> &[&str] {
+        &["dice"]
+    }
+    fn exec(&mut self, _args: &[&str], out: &mut dyn Write) -> CommandResult {
         let player: (i32, i32) = barbut_roll_generator();
         let npc: (i32, i32) = barbut_roll_generator();
-        println!("-------------------------------------------");
-        println!(
+
+        writeln!(out, "-------------------------------------------")?;
+        writeln!(
+            out,
             "You roll the dice... \n You got {0} and {1}",
             player.0, player.1
-        );
-        println!("NPC rolls the dice... \n He got {0} and {1}", npc.0, npc.1);
+        )?;
+        writeln!(out, "NPC rolls the dice... \n He got {0} and {1}", npc.0, npc.1)?;
         match (player.0 + player.1).cmp(&(npc.0 + npc.1)) {
-            Ordering::Greater => println!("You got more than the npc, you WIN!"),
-            Ordering::Equal => println!("You got the exact score as the npc, it's a TIE!"),
-            Ordering::Less => println!("You got less than the npc, it's a LOSS"),
+            Ordering::Greater => writeln!(out, "You got more than the npc, you WIN!")?,
+            Ordering::Equal => writeln!(out, "You got the exact score as the npc, it's a TIE!")?,
+            Ordering::Less => writeln!(out, "You got less than the npc, it's a LOSS")?,
         };
-        println!("-------------------------------------------\n");
+        writeln!(out, "-------------------------------------------")?;
+
+        Ok(Output::Nothing)
     }
 }
 
@@ -112,197 +741,1012 @@ impl MyCommand for StopCommand {
     fn get_name(&self) -> &str {
         "stop"
     }
-    fn exec(&mut self, _args: &[&str]) {
-        std::process::exit(0);
+    fn description(&self) -> &str {
+        "Stop the terminal, optionally with an exit code"
+    }
+    fn aliases(&self) -> &[&str] {
+        &["exit", "quit"]
+    }
+    fn args(&self) -> &[ArgSpec] {
+        const { &[optional("code")] }
+    }
+    fn exec(&mut self, args: &[&str], _out: &mut dyn Write) -> CommandResult {
+        let code = match args.first() {
+            Some(code) => match code.parse::<i32>() {
+                Ok(code) => code,
+                Err(_) => return Err(CommandError::Failed(format!("'{code}' is not an exit code"))),
+            },
+            None => 0,
+        };
+
+        Ok(Output::Stop(code))
     }
 }
 
-impl MyCommand for BookmarkCommand {
+impl MyCommand for SetCommand {
     fn get_name(&self) -> &str {
-        "bk"
+        "set"
     }
-    fn exec(&mut self, arg: &[&str]) {
-        let conn = match Connection::open("bookmarks.db") {
-            Ok(c) => c,
-            Err(e) => {
-                println! {"Couldn't connect, {e}"};
-                return;
+    fn description(&self) -> &str {
+        "Set a variable for $NAME, remove it when no value is given, or list them all"
+    }
+    fn args(&self) -> &[ArgSpec] {
+        const { &[optional("name"), rest("value")] }
+    }
+    fn exec(&mut self, args: &[&str], out: &mut dyn Write) -> CommandResult {
+        let mut vars = self.vars.borrow_mut();
+
+        let name = match args.first() {
+            Some(name) => name,
+            None => {
+                let mut names: Vec<&String> = vars.keys().collect();
+                names.sort();
+                for name in names {
+                    writeln!(out, "{name}={}", vars[name])?;
+                }
+                return Ok(Output::Nothing);
             }
         };
-        let create = r"
-            create table if not exists bookmarks (
-                name text    not null,
-                url  text not null
-                );
-            ";
-        match conn.execute(create, ()) {
-            Ok(_c) => (),
-            Err(e) => {
-                println!("Couldn't execute, {e}");
-                return;
+
+        let valid = name.chars().all(is_name_char) && !name.starts_with(|c: char| c.is_ascii_digit());
+        if !valid {
+            return Err(CommandError::Failed(format!("'{name}' is not a valid variable name")));
+        }
+
+        // `set NAME` alone removes the variable.
+        if args.len() == 1 {
+            vars.remove(*name);
+        } else {
+            vars.insert(name.to_string(), args[1..].join(" "));
+        }
+
+        Ok(Output::Nothing)
+    }
+}
+
+impl MyCommand for HelpCommand {
+    fn get_name(&self) -> &str {
+        "help"
+    }
+    fn description(&self) -> &str {
+        "List the commands, or show how to use one"
+    }
+    fn aliases(&self) -> &[&str] {
+        &["?"]
+    }
+    fn args(&self) -> &[ArgSpec] {
+        const { &[optional("command")] }
+    }
+    fn exec(&mut self, args: &[&str], out: &mut dyn Write) -> CommandResult {
+        let topics = self.topics.borrow();
+
+        let wanted = match args.first() {
+            Some(wanted) => wanted,
+            None => {
+                let width = topics.iter().map(|t| t.name.len()).max().unwrap_or(0);
+                for topic in topics.iter() {
+                    writeln!(out, "  {:width$}  {}", topic.name, topic.description)?;
+                }
+                writeln!(out, "Type 'help <command>' for details.")?;
+                writeln!(out, "Join commands with | to pass output on as arguments, > or >> to write it to a file,")?;
+                writeln!(out, "and &&, || or ; to run the next one after a success, a failure or either.")?;
+                return Ok(Output::Nothing);
             }
         };
 
-        let word = arg.first();
-        let key = match word {
-            Some(w) => w,
-            None => return,
+        let topic = topics
+            .iter()
+            .find(|t| t.name == *wanted || t.aliases.iter().any(|a| a == wanted));
+        let topic = match topic {
+            Some(topic) => topic,
+            None => return Err(CommandError::Failed(format!("no command named '{wanted}'"))),
         };
 
-        if *key == "add" {
-            let mut n = arg.get(1);
-            let name = match n {
-                Some(w) => w,
-                None => return,
-            };
-            n = arg.get(2);
-            let url = match n {
-                Some(w) => w,
-                None => return,
-            };
-            match conn.execute(
-                "insert into bookmarks (name, url) values (?1, ?2);",
-                (name, url),
-            ) {
-                Ok(_c) => (),
-                Err(e) => {
-                    println!("Couldn't execute, {e}");
-                    return;
-                }
-            };
+        writeln!(out, "{} - {}", topic.name, topic.description)?;
+        writeln!(out, "usage:")?;
+        for line in &topic.usage {
+            writeln!(out, "  {line}")?;
+        }
+        if !topic.actions.is_empty() {
+            writeln!(out, "actions:")?;
+            for (name, description) in &topic.actions {
+                writeln!(out, "  {name:8}  {description}")?;
+            }
+        }
+        if !topic.aliases.is_empty() {
+            writeln!(out, "aliases: {}", topic.aliases.join(", "))?;
         }
 
-        if *key == "search" {
-            let n = arg.get(1);
-            let search = match n {
-                Some(w) => w,
-                None => return,
-            };
-            let sql = format!("SELECT * FROM bookmarks WHERE name LIKE '%{search}%'");
+        Ok(Output::Nothing)
+    }
+}
 
-            let mut stmt = match conn.prepare(&sql) {
-                Ok(s) => s,
-                Err(e) => {
-                    println!("Couldn't prepare statement: {e}");
-                    return;
-                }
-            };
+impl MyCommand for BookmarkCommand {
+    fn get_name(&self) -> &str {
+        "bk"
+    }
+    fn description(&self) -> &str {
+        "Save and find bookmarks"
+    }
+    fn aliases(&self) -> &[&str] {
+        &["bookmark"]
+    }
+    fn actions(&self) -> &[Action] {
+        const {
+            &[
+                Action {
+                    name: "add",
+                    args: &[required("name"), required("url")],
+                    description: "Save a bookmark",
+                },
+                Action {
+                    name: "list",
+                    args: &[optional("tag")],
+                    description: "List every bookmark, or only those with the tag",
+                },
+                Action {
+                    name: "search",
+                    args: &[required("text"), rest("text"), value("limit", "n")],
+                    description: "Find bookmarks by name, url, tags or notes, tolerating typos",
+                },
+                Action {
+                    name: "rm",
+                    args: &[required("name")],
+                    description: "Delete a bookmark",
+                },
+                Action {
+                    name: "rename",
+                    args: &[required("name"), required("new name")],
+                    description: "Give a bookmark another name",
+                },
+                Action {
+                    name: "edit",
+                    args: &[required("name"), required("url")],
+                    description: "Point a bookmark at another url",
+                },
+                Action {
+                    name: "tag",
+                    args: &[required("name"), required("tag"), rest("tag")],
+                    description: "Add tags to a bookmark",
+                },
+                Action {
+                    name: "untag",
+                    args: &[required("name"), required("tag"), rest("tag")],
+                    description: "Remove tags from a bookmark",
+                },
+                Action {
+                    name: "note",
+                    args: &[required("name"), rest("text")],
+                    description: "Set the notes of a bookmark, or clear them",
+                },
+                Action {
+                    name: "import",
+                    args: &[required("file"), value("format", "html|json|csv"), flag("dry-run")],
+                    description: "Add the bookmarks from a file, skipping urls already saved",
+                },
+                Action {
+                    name: "export",
+                    args: &[required("file"), value("format", "html|json|csv")],
+                    description: "Write every bookmark to a file",
+                },
+            ]
+        }
+    }
+    fn exec(&mut self, arg: &[&str], out: &mut dyn Write) -> CommandResult {
+        let spec = self.actions().iter().find(|a| a.name == arg[0]).map_or(&[][..], |a| a.args);
+        let (arg, flags) = split_flags(arg, spec).map_err(CommandError::Failed)?;
+        let conn = self.connection()?;
 
-            let person_iter = match stmt.query_map([], |row| {
-                let name: String = match row.get("name") {
-                    Ok(v) => v,
-                    Err(e) => {
-                        println!("Failed to get name: {e}");
-                        return Ok(Bookmark {
-                            name: "".to_string(),
-                            url: "".to_string(),
-                        }); // skip this row
-                    }
+        match arg[0] {
+            "add" => {
+                let (name, url) = (arg[1], arg[2]);
+                check_unique(conn, name)?;
+                conn.execute(
+                    "insert into bookmarks (name, url, created, updated) values (?1, ?2, unixepoch(), unixepoch());",
+                    (name, url),
+                )?;
+
+                Ok(Output::Nothing)
+            }
+            "list" => {
+                let bookmarks = match arg.get(1) {
+                    Some(tag) => query_bookmarks(
+                        conn,
+                        "where b.name in (select name from bookmark_tags where tag = ?1) order by b.name",
+                        [tag],
+                    )?,
+                    None => query_bookmarks(conn, "order by b.name", [])?,
                 };
-                let url: String = match row.get("url") {
-                    Ok(v) => v,
-                    Err(e) => {
-                        println!("Failed to get url: {e}");
-                        return Ok(Bookmark {
-                            name: "".to_string(),
-                            url: "".to_string(),
-                        });
-                    }
+
+                write_bookmarks(out, &bookmarks)?;
+                Ok(Output::Nothing)
+            }
+            "search" => {
+                let limit = match flags.get("limit") {
+                    Some(limit) => limit
+                        .parse::<usize>()
+                        .map_err(|_| CommandError::Failed(format!("'{limit}' is not a limit")))?,
+                    None => SEARCH_LIMIT,
                 };
-                Ok(Bookmark { name, url })
-            }) {
-                Ok(iter) => iter,
-                Err(e) => {
-                    println!("Query failed: {e}");
-                    return;
+                let bookmarks = search(conn, &arg[1..].join(" "), limit)?;
+
+                write_bookmarks(out, &bookmarks)?;
+                Ok(Output::Nothing)
+            }
+            "rm" => {
+                let name = arg[1];
+                if conn.execute("delete from bookmarks where name = ?1;", [name])? == 0 {
+                    return Err(no_bookmark(name));
+                }
+                conn.execute("delete from bookmark_tags where name = ?1;", [name])?;
+
+                Ok(Output::Nothing)
+            }
+            "rename" => {
+                let (name, new_name) = (arg[1], arg[2]);
+                if name == new_name {
+                    return Ok(Output::Nothing);
+                }
+                check_unique(conn, new_name)?;
+                let changed = conn.execute(
+                    "update bookmarks set name = ?2, updated = unixepoch() where name = ?1;",
+                    (name, new_name),
+                )?;
+                if changed == 0 {
+                    return Err(no_bookmark(name));
+                }
+                conn.execute(
+                    "update bookmark_tags set name = ?2 where name = ?1;",
+                    (name, new_name),
+                )?;
+
+                Ok(Output::Nothing)
+            }
+            "edit" => {
+                let (name, url) = (arg[1], arg[2]);
+                let changed = conn.execute(
+                    "update bookmarks set url = ?2, updated = unixepoch() where name = ?1;",
+                    (name, url),
+                )?;
+                if changed == 0 {
+                    return Err(no_bookmark(name));
+                }
+
+                Ok(Output::Nothing)
+            }
+            "note" => {
+                let name = arg[1];
+                let changed = conn.execute(
+                    "update bookmarks set notes = ?2, updated = unixepoch() where name = ?1;",
+                    (name, arg[2..].join(" ")),
+                )?;
+                if changed == 0 {
+                    return Err(no_bookmark(name));
+                }
+
+                Ok(Output::Nothing)
+            }
+            "tag" | "untag" => {
+                let name = arg[1];
+                if !bookmark_exists(conn, name)? {
+                    return Err(no_bookmark(name));
                 }
-            };
 
-            for item in person_iter {
-                match item {
-                    Ok(b) => println!("name={}, url={}", b.name, b.url),
-                    Err(e) => println!("Error reading row: {e}"),
+                let sql = if arg[0] == "tag" {
+                    "insert or ignore into bookmark_tags (name, tag) values (?1, ?2);"
+                } else {
+                    "delete from bookmark_tags where name = ?1 and tag = ?2;"
+                };
+                for tag in &arg[2..] {
+                    conn.execute(sql, (name, tag))?;
                 }
+                conn.execute(
+                    "update bookmarks set updated = unixepoch() where name = ?1;",
+                    [name],
+                )?;
+
+                Ok(Output::Nothing)
+            }
+            "import" => {
+                let path = arg[1];
+                let text = fs::read_to_string(path)
+                    .map_err(|e| CommandError::Failed(format!("couldn't read {path}: {e}")))?;
+                let format = match flags.get("format") {
+                    Some(name) => Format::from_name(name).map_err(CommandError::Failed)?,
+                    None => Format::from_path(path).unwrap_or_else(|| Format::sniff(&text)),
+                };
+                let entries = format.read(&text).map_err(CommandError::Failed)?;
+
+                writeln!(out, "{}", import(conn, &entries, flags.contains_key("dry-run"))?)?;
+                Ok(Output::Nothing)
+            }
+            "export" => {
+                let path = arg[1];
+                let format = match flags.get("format") {
+                    Some(name) => Format::from_name(name).map_err(CommandError::Failed)?,
+                    None => Format::from_path(path).ok_or_else(|| {
+                        CommandError::Failed(format!("can't tell the format of {path}, pass --format html|json|csv"))
+                    })?,
+                };
+                let entries = export_entries(conn)?;
+                let text = format.write(&entries);
+                fs::write(path, text).map_err(|e| CommandError::Failed(format!("couldn't write {path}: {e}")))?;
+
+                writeln!(out, "exported {} bookmarks to {path}", entries.len())?;
+                Ok(Output::Nothing)
             }
+            other => Err(CommandError::Failed(format!("unknown bk action '{other}'"))),
         }
     }
 }
 
+impl BookmarkCommand {
+    fn connection(&mut self) -> Result<&Connection, CommandError> {
+        if self.conn.is_none() {
+            let mut conn = Connection::open(BOOKMARKS_DB)?;
+            migrate(&mut conn)?;
+            self.conn = Some(conn);
+        }
+        Ok(self.conn.as_ref().unwrap())
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), CommandError> {
+    let version: usize = conn.query_row("pragma user_version;", [], |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        return Err(CommandError::Failed(format!(
+            "{BOOKMARKS_DB} is at schema version {version}, this build only knows up to {}",
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+// Databases from before versioning are at 0 too, so this has to accept whatever they hold.
+fn create_bookmarks(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r"
+        create table if not exists bookmarks (
+            name text not null,
+            url  text not null
+            );
+        create table if not exists bookmark_tags (
+            name text not null,
+            tag  text not null,
+            unique (name, tag)
+            );
+        ",
+    )?;
+    add_timestamps(tx)
+}
+
+// Names were not unique before, the most recently added duplicate wins.
+fn index_bookmarks(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r"
+        delete from bookmarks where rowid not in (select max(rowid) from bookmarks group by name);
+        delete from bookmark_tags where name not in (select name from bookmarks);
+        create unique index bookmarks_name on bookmarks (name);
+        create index bookmark_tags_tag on bookmark_tags (tag);
+        ",
+    )
+}
+
+// Keeps a standalone full-text index in step with bookmarks and their tags, its rowids are the bookmarks' rowids.
+fn index_text(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r"
+        alter table bookmarks add column notes text not null default '';
+        create virtual table bookmarks_fts using fts5 (name, url, tags, notes);
+
+        create trigger bookmarks_fts_insert after insert on bookmarks begin
+            insert into bookmarks_fts (rowid, name, url, tags, notes)
+                values (new.rowid, new.name, new.url,
+                    (select group_concat(tag, ' ') from bookmark_tags where name = new.name), new.notes);
+        end;
+        create trigger bookmarks_fts_update after update on bookmarks begin
+            update bookmarks_fts set name = new.name, url = new.url, notes = new.notes where rowid = old.rowid;
+        end;
+        create trigger bookmarks_fts_delete after delete on bookmarks begin
+            delete from bookmarks_fts where rowid = old.rowid;
+        end;
+
+        create trigger bookmark_tags_fts_insert after insert on bookmark_tags begin
+            update bookmarks_fts
+                set tags = (select group_concat(tag, ' ') from bookmark_tags where name = new.name)
+                where rowid = (select rowid from bookmarks where name = new.name);
+        end;
+        create trigger bookmark_tags_fts_update after update on bookmark_tags begin
+            update bookmarks_fts
+                set tags = (select group_concat(tag, ' ') from bookmark_tags where name = new.name)
+                where rowid = (select rowid from bookmarks where name = new.name);
+        end;
+        create trigger bookmark_tags_fts_delete after delete on bookmark_tags begin
+            update bookmarks_fts
+                set tags = (select group_concat(tag, ' ') from bookmark_tags where name = old.name)
+                where rowid = (select rowid from bookmarks where name = old.name);
+        end;
+
+        insert into bookmarks_fts (rowid, name, url, tags, notes)
+            select rowid, name, url, (select group_concat(tag, ' ') from bookmark_tags t where t.name = b.name), notes
+            from bookmarks b;
+        ",
+    )
+}
+
+// Full-text matches come first, ranked by bm25 with the name weighted highest. Bookmarks the
+// index misses are then tried word by word against names and tags, allowing a few typos.
+fn search(conn: &Connection, text: &str, limit: usize) -> Result<Vec<Bookmark>, CommandError> {
+    let terms: Vec<String> = text.split_whitespace().map(|t| t.to_lowercase()).collect();
+    if terms.is_empty() {
+        return Err(CommandError::Failed("nothing to search for".to_string()));
+    }
+
+    // Every term is quoted so FTS5 operators in the input stay plain text, `*` makes it a prefix.
+    let expression = terms
+        .iter()
+        .map(|t| format!("\"{}\"*", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut found = query_bookmarks(
+        conn,
+        r"
+        join bookmarks_fts f on f.rowid = b.rowid
+        where bookmarks_fts match ?1
+        order by bm25(bookmarks_fts, 10.0, 1.0, 5.0, 2.0), b.name
+        ",
+        [expression],
+    )?;
+
+    let seen: HashSet<String> = found.iter().map(|b| b.name.clone()).collect();
+    let mut fuzzy: Vec<(usize, Bookmark)> = query_bookmarks(conn, "order by b.name", [])?
+        .into_iter()
+        .filter(|b| !seen.contains(&b.name))
+        .filter_map(|b| fuzzy_score(&terms, &format!("{} {}", b.name, b.tags)).map(|score| (score, b)))
+        .collect();
+    fuzzy.sort_by_key(|(score, _)| *score);
+
+    found.extend(fuzzy.into_iter().map(|(_, b)| b));
+    found.truncate(limit);
+    Ok(found)
+}
+
+// Roughly one typo per three letters, words shorter than that must match exactly.
+fn typo_allowance(term: &str) -> usize {
+    term.chars().count() / 3
+}
+
+// The summed distance of each term to its closest word, or None when some term has no close word.
+fn fuzzy_score(terms: &[String], text: &str) -> Option<usize> {
+    let text = text.to_lowercase();
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    terms.iter().try_fold(0, |total, term| {
+        let best = words
+            .iter()
+            .filter_map(|word| levensthein_distance(term, word, typo_allowance(term)))
+            .min()?;
+        Some(total + best)
+    })
+}
+
+fn no_bookmark(name: &str) -> CommandError {
+    CommandError::Failed(format!("no bookmark named '{name}'"))
+}
+
+fn bookmark_exists(conn: &Connection, name: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "select exists (select 1 from bookmarks where name = ?1);",
+        [name],
+        |row| row.get(0),
+    )
+}
+
+fn check_unique(conn: &Connection, name: &str) -> Result<(), CommandError> {
+    if bookmark_exists(conn, name)? {
+        return Err(CommandError::Failed(format!("a bookmark named '{name}' already exists")));
+    }
+    Ok(())
+}
+
+// Tables written before timestamps existed get the columns, with their rows dated to the upgrade.
+fn add_timestamps(conn: &Connection) -> rusqlite::Result<()> {
+    let has_created: bool = conn.query_row(
+        "select exists (select 1 from pragma_table_info('bookmarks') where name = 'created');",
+        [],
+        |row| row.get(0),
+    )?;
+    if has_created {
+        return Ok(());
+    }
+
+    conn.execute_batch(
+        r"
+        alter table bookmarks add column created integer not null default 0;
+        alter table bookmarks add column updated integer not null default 0;
+        update bookmarks set created = unixepoch(), updated = unixepoch();
+        ",
+    )
+}
+
+// Only ever given fixed SQL, values go through `params`.
+fn query_bookmarks<P: rusqlite::Params>(
+    conn: &Connection,
+    clauses: &'static str,
+    params: P,
+) -> rusqlite::Result<Vec<Bookmark>> {
+    let sql = format!(
+        r"
+        select b.name, b.url, b.notes,
+            datetime(b.created, 'unixepoch') as created,
+            datetime(b.updated, 'unixepoch') as updated,
+            (select group_concat(tag, ',' order by tag) from bookmark_tags t where t.name = b.name) as tags
+        from bookmarks b
+        {clauses};
+        "
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let bookmarks = stmt.query_map(params, |row| {
+        Ok(Bookmark {
+            name: row.get("name")?,
+            url: row.get("url")?,
+            tags: row.get::<_, Option<String>>("tags")?.unwrap_or_default(),
+            notes: row.get("notes")?,
+            created: row.get("created")?,
+            updated: row.get("updated")?,
+        })
+    })?;
+    bookmarks.collect()
+}
+
+fn write_bookmarks(out: &mut dyn Write, bookmarks: &[Bookmark]) -> io::Result<()> {
+    for b in bookmarks {
+        write!(out, "name={}, url={}, tags={}", b.name, b.url, b.tags)?;
+        if !b.notes.is_empty() {
+            write!(out, ", notes={}", b.notes)?;
+        }
+        writeln!(out, ", created={}, updated={}", b.created, b.updated)?;
+    }
+    Ok(())
+}
+
+fn export_entries(conn: &Connection) -> rusqlite::Result<Vec<Entry>> {
+    let mut stmt = conn.prepare(
+        r"
+        select b.name, b.url, b.notes, b.created, b.updated,
+            (select group_concat(tag, char(10) order by tag) from bookmark_tags t where t.name = b.name) as tags
+        from bookmarks b
+        order by b.name;
+        ",
+    )?;
+    let entries = stmt.query_map([], |row| {
+        let tags: Option<String> = row.get("tags")?;
+        Ok(Entry {
+            name: row.get("name")?,
+            url: row.get("url")?,
+            tags: tags.map_or(Vec::new(), |t| t.lines().map(str::to_string).collect()),
+            notes: row.get("notes")?,
+            created: row.get("created")?,
+            updated: row.get("updated")?,
+        })
+    })?;
+    entries.collect()
+}
+
+// Everything runs in one transaction, a dry run simply never commits it.
+fn import(conn: &Connection, entries: &[Entry], dry_run: bool) -> Result<String, CommandError> {
+    let tx = conn.unchecked_transaction()?;
+    let mut urls: HashSet<String> = HashSet::new();
+    let mut names: HashSet<String> = HashSet::new();
+    {
+        let mut stmt = tx.prepare("select name, url from bookmarks;")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (name, url) = row?;
+            names.insert(name);
+            urls.insert(url);
+        }
+    }
+
+    let (mut imported, mut duplicates, mut renamed, mut invalid) = (0, 0, 0, 0);
+    for entry in entries {
+        if entry.url.is_empty() {
+            invalid += 1;
+            continue;
+        }
+        if !urls.insert(entry.url.clone()) {
+            duplicates += 1;
+            continue;
+        }
+
+        let base = if entry.name.is_empty() { entry.url.clone() } else { entry.name.clone() };
+        let mut name = base.clone();
+        let mut n = 2;
+        while names.contains(&name) {
+            name = format!("{base} ({n})");
+            n += 1;
+        }
+        if name != base {
+            renamed += 1;
+        }
+
+        tx.execute(
+            r"
+            insert into bookmarks (name, url, notes, created, updated)
+                values (?1, ?2, ?3, coalesce(?4, unixepoch()), coalesce(?5, ?4, unixepoch()));
+            ",
+            (&name, &entry.url, &entry.notes, entry.created, entry.updated),
+        )?;
+        for tag in &entry.tags {
+            tx.execute(
+                "insert or ignore into bookmark_tags (name, tag) values (?1, ?2);",
+                (&name, tag),
+            )?;
+        }
+        names.insert(name);
+        imported += 1;
+    }
+
+    if !dry_run {
+        tx.commit()?;
+    }
+
+    let mut summary = format!(
+        "{} {imported} bookmarks, skipped {duplicates} duplicates",
+        if dry_run { "would import" } else { "imported" }
+    );
+    if invalid > 0 {
+        summary.push_str(&format!(" and {invalid} entries without a url"));
+    }
+    if renamed > 0 {
+        summary.push_str(&format!(", {renamed} renamed to avoid a name clash"));
+    }
+    Ok(summary)
+}
+
+impl Completer for CommandCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let start = before.len() - before.trim_start().len();
+        let word = &before[start..];
+
+        // Only the command name is completed, arguments are free text.
+        if word.contains(char::is_whitespace) {
+            return Ok((pos, Vec::new()));
+        }
+
+        let candidates = self
+            .names
+            .iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| Pair {
+                display: name.clone(),
+                replacement: format!("{name} "),
+            })
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for CommandCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for CommandCompleter {}
+
+impl Validator for CommandCompleter {}
+
+impl Helper for CommandCompleter {}
+
+fn history_path() -> PathBuf {
+    match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() => PathBuf::from(home).join(HISTORY_FILE),
+        _ => PathBuf::from(HISTORY_FILE),
+    }
+}
+
 impl Terminal {
     fn new() -> Self {
-        let commands: Vec<Box<dyn MyCommand>> = vec![Box::new(StopCommand {})];
+        let vars = Rc::new(RefCell::new(HashMap::new()));
+        let topics = Rc::new(RefCell::new(Vec::new()));
 
-        Terminal { commands }
+        let mut terminal = Terminal {
+            commands: Vec::new(),
+            vars: vars.clone(),
+            topics: topics.clone(),
+        };
+        terminal.register(Box::new(HelpCommand { topics }));
+        terminal.register(Box::new(StopCommand {}));
+        terminal.register(Box::new(SetCommand { vars }));
+
+        terminal
     }
 
     fn register(&mut self, arg: Box<dyn MyCommand>) {
+        self.topics.borrow_mut().push(HelpTopic {
+            name: arg.get_name().to_string(),
+            aliases: arg.aliases().iter().map(|a| a.to_string()).collect(),
+            description: arg.description().to_string(),
+            usage: usage(arg.as_ref()),
+            actions: arg
+                .actions()
+                .iter()
+                .map(|a| (a.name.to_string(), a.description.to_string()))
+                .collect(),
+        });
         self.commands.push(arg);
     }
 
-    fn suggestions(&self, s: &str) {
+    // Names the typed text is a prefix of come first, then those within a few typos, closest first.
+    fn suggestions(&self, s: &str) -> String {
         let s_lower = s.to_lowercase();
-        let mut min = usize::MAX;
-        let mut suggestion = " ";
-        for registered in &self.commands {
-            let dist = levensthein_distance(
-                registered.get_name(),
-                &s_lower,
-                registered.get_name().chars().count(),
-                s_lower.chars().count(),
-            );
-            if dist < min {
-                min = dist;
-                suggestion = registered.get_name();
+        let max = typo_allowance(&s_lower).max(1);
+
+        let mut candidates: Vec<(bool, usize, &str)> = self
+            .commands
+            .iter()
+            .flat_map(|c| std::iter::once(c.get_name()).chain(c.aliases().iter().copied()))
+            .filter_map(|name| {
+                let lower = name.to_lowercase();
+                let prefix = !s_lower.is_empty() && lower.starts_with(&s_lower);
+                let distance = levensthein_distance(&s_lower, &lower, max);
+                match (prefix, distance) {
+                    (true, _) => Some((false, 0, name)),
+                    // Rewriting every character of a short name like `?` is not a resemblance.
+                    (false, Some(distance)) if distance < lower.chars().count() => Some((true, distance, name)),
+                    (false, _) => None,
+                }
+            })
+            .collect();
+        candidates.sort();
+        candidates.dedup_by_key(|(_, _, name)| *name);
+
+        let names: Vec<String> = candidates
+            .iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, _, name)| format!("'{name}'"))
+            .collect();
+        match names.split_last() {
+            None => format!("'{s}' is not a valid function. Type 'help' to list the commands."),
+            Some((last, [])) => format!("'{s}' is not a valid function. Did you mean to write {last}?"),
+            Some((last, rest)) => format!(
+                "'{s}' is not a valid function. Did you mean to write {} or {last}?",
+                rest.join(", ")
+            ),
+        }
+    }
+
+    // Runs one line and reports every outcome here, so commands never print or exit themselves.
+    fn execute(&mut self, line: &str) -> Status {
+        let report = |e: ParseError| {
+            let pointer = " ".repeat(e.column - 1);
+            eprintln!("{line}\n{pointer}^ {}", e.message);
+        };
+
+        let steps = match tokenize(line).and_then(parse_steps) {
+            Ok(steps) => steps,
+            Err(e) => {
+                report(e);
+                return Status::Failure;
             }
+        };
+
+        // A skipped step keeps the status before it, so `a && b || c` runs c when a fails.
+        let mut status = Status::Success;
+        for step in &steps {
+            let run = match step.after {
+                Operator::And => status == Status::Success,
+                Operator::Or => status == Status::Failure,
+                _ => true,
+            };
+            if run {
+                // Expanded only now, an earlier step may have set the variables.
+                let vars = self.vars.borrow();
+                let stages: Result<Vec<Invocation>, ParseError> =
+                    step.stages.iter().map(|stage| stage.expand(&vars)).collect();
+                drop(vars);
+                status = match stages {
+                    Ok(stages) => self.run_pipeline(&stages),
+                    Err(e) => {
+                        report(e);
+                        Status::Failure
+                    }
+                };
+            }
+            if let Status::Stop(_) = status {
+                break;
+            }
+        }
+        status
+    }
+
+    // Each stage's output becomes the next one's trailing arguments, one per line. The status
+    // is the last stage's, like a shell without pipefail.
+    fn run_pipeline(&mut self, stages: &[Invocation]) -> Status {
+        let mut input: Vec<String> = Vec::new();
+        let mut status = Status::Success;
+
+        for (i, stage) in stages.iter().enumerate() {
+            let mut buffer: Vec<u8> = Vec::new();
+            let mut file;
+            let mut stdout;
+            let out: &mut dyn Write = match &stage.redirect {
+                Some((path, append)) => {
+                    let opened = OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .append(*append)
+                        .truncate(!*append)
+                        .open(path);
+                    file = match opened {
+                        Ok(file) => file,
+                        Err(e) => {
+                            eprintln!("Couldn't open {path}, error: {e}");
+                            return Status::Failure;
+                        }
+                    };
+                    &mut file
+                }
+                None if i + 1 == stages.len() => {
+                    stdout = io::stdout().lock();
+                    &mut stdout
+                }
+                None => &mut buffer,
+            };
+
+            let mut words: Vec<&str> = stage.words.iter().map(|w| w.as_str()).collect();
+            words.extend(input.iter().map(|line| line.as_str()));
+            status = self.run_command(&words, out);
+            if let Status::Stop(_) = status {
+                return status;
+            }
+
+            input = String::from_utf8_lossy(&buffer)
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.to_string())
+                .collect();
         }
 
-        println!("'{s}' is not a valid function. Did you mean to write '{suggestion}'?");
+        status
     }
 
-    fn run(&mut self) {
-        let file = match File::open("commands.txt") {
+    fn run_command(&mut self, words: &[&str], out: &mut dyn Write) -> Status {
+        let command_name = words[0];
+        let args = &words[1..];
+
+        let registered = self
+            .commands
+            .iter_mut()
+            .find(|c| c.get_name() == command_name || c.aliases().contains(&command_name));
+        let registered = match registered {
+            Some(registered) => registered,
+            None => {
+                eprintln!("{}", self.suggestions(command_name));
+                return Status::Failure;
+            }
+        };
+
+        if let Err(e) = check_args(registered.as_ref(), args) {
+            eprintln!("{command_name}: {e}");
+            return Status::Failure;
+        }
+
+        let result = registered.exec(args, out);
+        if let Err(e) = out.flush() {
+            eprintln!("{command_name}: {e}");
+            return Status::Failure;
+        }
+        match result {
+            Ok(Output::Nothing) => Status::Success,
+            Ok(Output::Stop(code)) => Status::Stop(code),
+            Err(e) => {
+                eprintln!("{command_name}: {e}");
+                Status::Failure
+            }
+        }
+    }
+
+    // Returns the exit code: the one given to `stop`, otherwise 1 if any line failed.
+    fn run_script(&mut self, path: &str, strict: bool) -> i32 {
+        let file = match File::open(path) {
             Ok(exel) => exel,
             Err(e) => {
-                println!("Couldn't open file, error: {e}");
-                return;
+                eprintln!("Couldn't open file, error: {e}");
+                return 1;
             }
         };
 
         let reader = BufReader::new(file);
+        let mut code = 0;
 
-        for lines in reader.lines() {
+        for (number, lines) in reader.lines().enumerate() {
             let line = match lines {
                 Ok(text) => text,
                 Err(e) => {
-                    println!("Couldn't read line, error {e}");
-                    continue;
+                    eprintln!("Couldn't read line, error {e}");
+                    return 1;
                 }
             };
 
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
+            match self.execute(&line) {
+                Status::Success => (),
+                Status::Failure if strict => {
+                    eprintln!("{path}:{}: stopping, --strict is set", number + 1);
+                    return 1;
+                }
+                Status::Failure => code = 1,
+                Status::Stop(stop) => return stop,
+            }
+        }
+
+        code
+    }
+
+    fn run_interactive(&mut self) -> i32 {
+        let names = self
+            .commands
+            .iter()
+            .flat_map(|c| std::iter::once(c.get_name()).chain(c.aliases().iter().copied()))
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut editor: Editor<CommandCompleter, FileHistory> = match Editor::new() {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Couldn't start the prompt, error: {e}");
+                return 1;
             }
+        };
+        editor.set_helper(Some(CommandCompleter { names }));
 
-            let mut words = line.split_whitespace();
-            let command_name = match words.next() {
-                Some(name) => name,
-                None => continue,
-            };
-            let args: Vec<&str> = words.collect();
+        // A missing history file just means this is the first session.
+        let history = history_path();
+        let _ = editor.load_history(&history);
 
-            let mut wrong: bool = true;
-            for registered in &mut self.commands {
-                if command_name == registered.get_name() {
-                    registered.exec(&args);
-                    wrong = false;
+        let code = loop {
+            let line = match editor.readline("> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break 0,
+                Err(e) => {
+                    eprintln!("Couldn't read line, error {e}");
+                    return 1;
                 }
+            };
+
+            if !line.trim().is_empty() {
+                let _ = editor.add_history_entry(line.as_str());
             }
 
-            if wrong {
-                self.suggestions(command_name);
+            if let Status::Stop(code) = self.execute(&line) {
+                break code;
             }
+        };
+
+        if let Err(e) = editor.save_history(&history) {
+            eprintln!("Couldn't save history, error {e}");
         }
+        code
     }
 }
 
@@ -313,7 +1757,254 @@ fn main() {
     terminal.register(Box::new(CountCommand {}));
     terminal.register(Box::new(TimesCommand { count: 0 }));
     terminal.register(Box::new(BarbutCommand {}));
-    terminal.register(Box::new(BookmarkCommand {}));
+    terminal.register(Box::new(BookmarkCommand { conn: None }));
+
+    let mut strict = false;
+    let mut script = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--strict" => strict = true,
+            _ => script = Some(arg),
+        }
+    }
+
+    // With a path the commands are read from that script, otherwise from the prompt.
+    let code = match script {
+        Some(path) => terminal.run_script(&path, strict),
+        None => terminal.run_interactive(),
+    };
+
+    std::process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Words as typed with variables shown as `${NAME}`, operators as their symbol.
+    fn show_word(word: &Word) -> String {
+        word.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Var(name, _) => format!("${{{name}}}"),
+            })
+            .collect()
+    }
+
+    fn show_tokens(line: &str) -> Result<Vec<String>, (usize, String)> {
+        let tokens = tokenize(line).map_err(|e| (e.column, e.message))?;
+        Ok(tokens
+            .iter()
+            .map(|token| match token {
+                Token::Word(word) => show_word(word),
+                Token::Op(op, _) => op.symbol().to_string(),
+            })
+            .collect())
+    }
 
-    terminal.run();
+    // One string per step: the operator before it, then its stages.
+    fn show_steps(line: &str) -> Result<Vec<String>, (usize, String)> {
+        let steps = tokenize(line).and_then(parse_steps).map_err(|e| (e.column, e.message))?;
+        Ok(steps
+            .iter()
+            .map(|step| {
+                let stages: Vec<String> = step
+                    .stages
+                    .iter()
+                    .map(|stage| {
+                        let mut text: Vec<String> = stage.words.iter().map(show_word).collect();
+                        if let Some((path, append)) = &stage.redirect {
+                            text.push(if *append { ">>" } else { ">" }.to_string());
+                            text.push(show_word(path));
+                        }
+                        text.join(" ")
+                    })
+                    .collect();
+                format!("{} {}", step.after.symbol(), stages.join(" | "))
+            })
+            .collect())
+    }
+
+    #[test]
+    fn tokenizes() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("  ping   pong ", &["ping", "pong"]),
+            (r#"echo 'a b' "c d""#, &["echo", "a b", "c d"]),
+            (r#"'' """#, &["", ""]),
+            (r#"it's a 'b"c"d"#, &["its a bcd"]),
+            (r"a\ b \'x \\", &["a b", "'x", "\\"]),
+            (r#""q\"\\\$ \n""#, &[r#"q"\$ \n"#]),
+            (r#"'no \escape "here"'"#, &[r#"no \escape "here""#]),
+            ("a#b # the rest is a comment", &["a#b"]),
+            ("# only a comment", &[]),
+            (r"\# not a comment", &["#", "not", "a", "comment"]),
+            ("'#' \"#\"", &["#", "#"]),
+            ("$A x${NAME}y", &["${A}", "x${NAME}y"]),
+            (r#""$A-$B" '$A'"#, &["${A}-${B}", "$A"]),
+            ("$ $1 a$ ${A}1", &["$", "$1", "a$", "${A}1"]),
+            (r"\$A", &["$A"]),
+            ("a|b||c>d>>e&&f;g", &["a", "|", "b", "||", "c", ">", "d", ">>", "e", "&&", "f", ";", "g"]),
+            ("a & b&c", &["a", "&", "b&c"]),
+            ("'a|b' \"c;d\" e\\>f", &["a|b", "c;d", "e>f"]),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(show_tokens(line), Ok(expected.iter().map(|t| t.to_string()).collect()), "{line}");
+        }
+    }
+
+    #[test]
+    fn reports_tokenize_errors() {
+        let cases: &[(&str, usize, &str)] = &[
+            ("echo 'abc", 6, "unterminated single quote"),
+            ("echo \"abc", 6, "unterminated double quote"),
+            ("echo \"a'b", 6, "unterminated double quote"),
+            (r"ab\", 3, "nothing to escape at end of line"),
+            ("x ${1}", 3, "bad variable name"),
+            ("${}", 1, "bad variable name"),
+            ("x \"${A\"", 4, "missing '}'"),
+            ("x ${A-b}", 3, "missing '}'"),
+        ];
+
+        for (line, column, message) in cases {
+            assert_eq!(show_tokens(line), Err((*column, message.to_string())), "{line}");
+        }
+    }
+
+    #[test]
+    fn records_columns() {
+        let tokens = tokenize("ab $X \"c${Y}\" || d").unwrap();
+        let columns: Vec<usize> = tokens
+            .iter()
+            .flat_map(|token| match token {
+                Token::Word(word) => word
+                    .0
+                    .iter()
+                    .filter_map(|part| match part {
+                        Part::Var(_, column) => Some(*column),
+                        Part::Text(_) => None,
+                    })
+                    .collect(),
+                Token::Op(_, column) => vec![*column],
+            })
+            .collect();
+        assert_eq!(columns, [4, 9, 15]);
+    }
+
+    #[test]
+    fn parses_steps() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            (";", &[]),
+            ("# nothing", &[]),
+            ("ping", &["; ping"]),
+            ("ping;", &["; ping"]),
+            ("a b | c > f", &["; a b | c > f"]),
+            ("a >> log | b", &["; a >> log | b"]),
+            ("a > x > y", &["; a > y"]),
+            ("a && b || c ; d ;", &["; a", "&& b", "|| c", "; d"]),
+            ("a|b&&c|d", &["; a | b", "&& c | d"]),
+            ("a '&&' b", &["; a && b"]),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(show_steps(line), Ok(expected.iter().map(|s| s.to_string()).collect()), "{line}");
+        }
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let cases: &[(&str, usize, &str)] = &[
+            ("| a", 1, "missing command before '|'"),
+            ("; a", 1, "missing command before ';'"),
+            ("a && && b", 6, "missing command before '&&'"),
+            ("a | || b", 5, "missing command before '||'"),
+            ("a >", 3, "missing file after '>'"),
+            ("a >> | b", 3, "missing file after '>>'"),
+            ("a |", 3, "missing command at the end of the line"),
+            ("a &&", 3, "missing command at the end of the line"),
+            ("a || b ||", 8, "missing command at the end of the line"),
+            ("> f", 1, "nothing to redirect"),
+        ];
+
+        for (line, column, message) in cases {
+            assert_eq!(show_steps(line), Err((*column, message.to_string())), "{line}");
+        }
+    }
+
+    fn terminal() -> Terminal {
+        let mut terminal = Terminal::new();
+        terminal.register(Box::new(PingCommand {}));
+        terminal.register(Box::new(CountCommand {}));
+        terminal.register(Box::new(TimesCommand { count: 0 }));
+        terminal.register(Box::new(BarbutCommand {}));
+        terminal.register(Box::new(BookmarkCommand { conn: None }));
+        terminal
+    }
+
+    #[test]
+    fn suggests_commands() {
+        let terminal = terminal();
+        let cases = [
+            ("TIMES", "Did you mean to write 'times'?"),
+            ("pnig", "Did you mean to write 'ping'?"),
+            ("quti", "Did you mean to write 'quit'?"),
+            // Prefixes come before typos, however short the typo's distance.
+            ("bo", "Did you mean to write 'bookmark' or 'bk'?"),
+            // `?` is one edit away from any single character but resembles none of them.
+            ("s", "Did you mean to write 'set' or 'stop'?"),
+            ("x", "Type 'help' to list the commands."),
+            ("zzzzzz", "Type 'help' to list the commands."),
+        ];
+        for (typed, expected) in cases {
+            let message = terminal.suggestions(typed);
+            assert_eq!(message, format!("'{typed}' is not a valid function. {expected}"));
+        }
+    }
+
+    #[test]
+    fn checks_arguments() {
+        let bookmarks = BookmarkCommand { conn: None };
+        let cases: [(&dyn MyCommand, &[&str], bool); 8] = [
+            // Commands without declared arguments ignore extra ones, as they always have.
+            (&PingCommand {}, &["abc"], true),
+            (&TimesCommand { count: 0 }, &["a", "b"], true),
+            (&BarbutCommand {}, &["abc"], true),
+            (&CountCommand {}, &["a", "b", "c"], true),
+            (&bookmarks, &["add", "foxes", "https://foxes.example"], true),
+            (&bookmarks, &["add", "foxes"], false),
+            (&bookmarks, &["search"], false),
+            (&bookmarks, &["fly"], false),
+        ];
+        for (command, args, ok) in cases {
+            assert_eq!(check_args(command, args).is_ok(), ok, "{} {:?}", command.get_name(), args);
+        }
+    }
+
+    #[test]
+    fn expands_when_the_step_runs() {
+        let mut vars = HashMap::new();
+        vars.insert("A".to_string(), "1".to_string());
+        let words: Vec<Word> = tokenize("x${A}y \"$A $A\" $LAB_6_UNSET")
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word),
+                Token::Op(..) => None,
+            })
+            .collect();
+
+        assert_eq!(words[0].expand(&vars).ok(), Some("x1y".to_string()));
+        assert_eq!(words[1].expand(&vars).ok(), Some("1 1".to_string()));
+        let e = words[2].expand(&vars).err().unwrap();
+        assert_eq!((e.column, e.message), (16, "undefined variable 'LAB_6_UNSET'".to_string()));
+
+        let mut terminal = Terminal::new();
+        assert!(terminal.execute("set A 1; set B $A && set C ${B}2") == Status::Success);
+        assert_eq!(terminal.vars.borrow().get("C").map(String::as_str), Some("12"));
+        assert!(terminal.execute("set D $LAB_6_UNSET || set D fallback") == Status::Success);
+        assert_eq!(terminal.vars.borrow().get("D").map(String::as_str), Some("fallback"));
+    }
 }
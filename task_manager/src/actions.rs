@@ -0,0 +1,380 @@
+use crate::backend::sched::{self, Scheduling, IO_CLASSES, POLICIES};
+use crate::backend::signals::{self, Identity, Order, Signal, SIGNALS};
+use crate::i18n::{tr, trf};
+use crate::{FilterType, TaskManager};
+use eframe::egui;
+use std::collections::HashMap;
+
+#[derive(PartialEq, Clone, Copy)]
+enum Scope {
+    Subtree(Order),
+    Filter,
+    Group,
+    Session,
+}
+
+// What a target must still belong to when the signal is finally sent.
+#[derive(Clone, Copy)]
+enum Membership {
+    Any,
+    Group(u32),
+    Session(u32),
+}
+
+pub struct PendingSignal {
+    what: String,
+    signal: Signal,
+    membership: Membership,
+    // Recorded when the preview opens, the user may confirm much later.
+    targets: Vec<(Identity, String)>,
+}
+
+pub struct SignalState {
+    pub signal: Signal,
+    pub pending: Option<PendingSignal>,
+    pub report: Option<String>,
+}
+
+impl Default for SignalState {
+    fn default() -> Self {
+        Self {
+            signal: Signal::Term,
+            pending: None,
+            report: None,
+        }
+    }
+}
+
+// Current scheduling of the process shown in Details and the values being edited.
+#[derive(Default)]
+pub struct SchedEditor {
+    pid: Option<u32>,
+    pub stale: bool,
+    current: Option<Result<Scheduling, String>>,
+    edit: Option<Scheduling>,
+    report: Option<Result<String, String>>,
+}
+
+impl TaskManager {
+    pub fn signal_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(tr("Signal"), |ui| {
+            egui::ComboBox::from_label("signal")
+                .selected_text(self.signals.signal.name())
+                .show_ui(ui, |ui| {
+                    for signal in SIGNALS {
+                        ui.selectable_value(&mut self.signals.signal, signal, signal.name());
+                    }
+                });
+            ui.separator();
+
+            let has_selection = self.selected.is_some();
+            let mut scope = None;
+
+            if ui
+                .add_enabled(has_selection, egui::Button::new(tr("Subtree, children first")))
+                .clicked()
+            {
+                scope = Some(Scope::Subtree(Order::ChildrenFirst));
+            }
+            if ui
+                .add_enabled(has_selection, egui::Button::new(tr("Subtree, parent first")))
+                .clicked()
+            {
+                scope = Some(Scope::Subtree(Order::ParentFirst));
+            }
+            if ui
+                .add_enabled(has_selection, egui::Button::new(tr("Process group")))
+                .clicked()
+            {
+                scope = Some(Scope::Group);
+            }
+            if ui
+                .add_enabled(has_selection, egui::Button::new(tr("Session")))
+                .clicked()
+            {
+                scope = Some(Scope::Session);
+            }
+            if ui.button(tr("Everything matching the filter")).clicked() {
+                scope = Some(Scope::Filter);
+            }
+
+            if let Some(scope) = scope {
+                self.prepare_signal(scope);
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn prepare_signal(&mut self, scope: Scope) {
+        let all = TaskManager::data_table_view(
+            &self.stats.processes,
+            self.criteria,
+            self.sort_type,
+            FilterType::All,
+            &self.user,
+        );
+        let names: HashMap<u32, &str> = all.iter().map(|p| (p.pid, p.name.as_str())).collect();
+        let selected = self.selected.and_then(|pid| all.iter().find(|p| p.pid == pid).copied());
+
+        let (what, membership, pids) = match (scope, selected) {
+            (Scope::Subtree(order), Some(root)) => (
+                trf("subtree of {} ({})", &[&root.name, &root.pid]),
+                Membership::Any,
+                signals::subtree(root, order),
+            ),
+            (Scope::Group, Some(root)) => match signals::group_and_session(root.pid) {
+                Some((pgrp, _)) => (
+                    trf("process group {}", &[&pgrp]),
+                    Membership::Group(pgrp),
+                    signals::process_group(pgrp).unwrap_or_default(),
+                ),
+                None => (trf("process group of {}", &[&root.pid]), Membership::Any, Vec::new()),
+            },
+            (Scope::Session, Some(root)) => match signals::group_and_session(root.pid) {
+                Some((_, sid)) => (
+                    trf("session {}", &[&sid]),
+                    Membership::Session(sid),
+                    signals::session(sid).unwrap_or_default(),
+                ),
+                None => (trf("session of {}", &[&root.pid]), Membership::Any, Vec::new()),
+            },
+            (Scope::Filter, _) => (
+                tr("every process matching the current filter").to_string(),
+                Membership::Any,
+                TaskManager::data_table_view(
+                    &self.stats.processes,
+                    self.criteria,
+                    self.sort_type,
+                    self.filter,
+                    &self.user,
+                )
+                .iter()
+                .map(|p| p.pid)
+                .collect(),
+            ),
+            _ => {
+                self.signals.report = Some(tr("The selected process is no longer running.").to_string());
+                return;
+            }
+        };
+
+        // Never signal ourselves, the list would otherwise include the task manager.
+        // Processes that already exited are dropped here.
+        let own = std::process::id();
+        let targets = pids
+            .into_iter()
+            .filter(|pid| *pid != own)
+            .filter_map(signals::identity)
+            .map(|id| (id, names.get(&id.pid).unwrap_or(&tr("Unknown")).to_string()))
+            .collect();
+
+        self.signals.pending = Some(PendingSignal {
+            what,
+            signal: self.signals.signal,
+            membership,
+            targets,
+        });
+        self.signals.report = None;
+    }
+
+    pub fn signal_window(&mut self, ctx: &egui::Context) {
+        let mut send = false;
+        let mut cancel = false;
+
+        if let Some(pending) = &self.signals.pending {
+            egui::Window::new(tr("Confirm signal"))
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(trf(
+                        "Send {} to {}: {} processes",
+                        &[&pending.signal.name(), &pending.what, &pending.targets.len()],
+                    ));
+                    ui.separator();
+
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        egui::Grid::new("signal_targets").striped(true).show(ui, |ui| {
+                            for (id, name) in &pending.targets {
+                                ui.label(id.pid.to_string());
+                                ui.label(name);
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        send = ui
+                            .add_enabled(!pending.targets.is_empty(), egui::Button::new(tr("Send")))
+                            .clicked();
+                        cancel = ui.button(tr("Cancel")).clicked();
+                    });
+                });
+        }
+
+        if send {
+            if let Some(pending) = self.signals.pending.take() {
+                // Pids can be reused while the preview is open, so check each one again right before sending.
+                let targets: Vec<Identity> = pending.targets.iter().map(|(id, _)| *id).collect();
+                let (pids, gone) = signals::still_running(&targets, |now| match pending.membership {
+                    Membership::Any => true,
+                    Membership::Group(pgrp) => now.pgrp == pgrp,
+                    Membership::Session(sid) => now.session == sid,
+                });
+                let failed = signals::send_all(&pids, pending.signal);
+
+                let mut report = trf(
+                    "{} sent to {} of {} processes",
+                    &[&pending.signal.name(), &(pids.len() - failed.len()), &targets.len()],
+                );
+                for pid in gone {
+                    report.push('\n');
+                    report.push_str(&trf("{}: exited or replaced since the preview, skipped", &[&pid]));
+                }
+                for (pid, e) in failed {
+                    report.push_str(&format!("\n{}: {}", pid, e));
+                }
+                self.signals.report = Some(report);
+            }
+        } else if cancel {
+            self.signals.pending = None;
+        }
+
+        let mut close_report = false;
+        if let Some(report) = &self.signals.report {
+            egui::Window::new(tr("Signal result")).show(ctx, |ui| {
+                ui.label(report);
+                close_report = ui.button(tr("Close")).clicked();
+            });
+        }
+        if close_report {
+            self.signals.report = None;
+        }
+    }
+
+    pub fn scheduling_section(&mut self, ui: &mut egui::Ui, pid: u32) {
+        let editor = &mut self.sched;
+        if editor.pid != Some(pid) {
+            editor.pid = Some(pid);
+            editor.edit = None;
+            editor.report = None;
+            editor.stale = true;
+        }
+        if editor.stale {
+            editor.current = Some(sched::scheduling(pid).map_err(|e| e.to_string()));
+            editor.stale = false;
+        }
+
+        ui.separator();
+        ui.strong(tr("Scheduling"));
+
+        let current = match &editor.current {
+            Some(Ok(current)) => current,
+            Some(Err(e)) => {
+                ui.label(trf("Couldn't read the scheduling of {}: {}", &[&pid, e]));
+                return;
+            }
+            None => return,
+        };
+        let edit = editor.edit.get_or_insert_with(|| current.clone());
+        let cores = self.stats.cores.max(1);
+        let mut result: Option<Result<String, std::io::Error>> = None;
+
+        egui::Grid::new("scheduling").num_columns(3).striped(true).show(ui, |ui| {
+            ui.strong(tr("Affinity"));
+            ui.label(sched::cpu_list(&current.affinity));
+            ui.horizontal_wrapped(|ui| {
+                for cpu in 0..cores {
+                    let mut on = edit.affinity.contains(&cpu);
+                    if ui.checkbox(&mut on, cpu.to_string()).changed() {
+                        if on {
+                            edit.affinity.push(cpu);
+                            edit.affinity.sort();
+                        } else {
+                            edit.affinity.retain(|c| *c != cpu);
+                        }
+                    }
+                }
+                if ui.button(tr("Apply")).clicked() {
+                    result = Some(
+                        sched::set_affinity(pid, &edit.affinity)
+                            .map(|_| trf("Affinity set to {}", &[&sched::cpu_list(&edit.affinity)])),
+                    );
+                }
+            });
+            ui.end_row();
+
+            ui.strong(tr("Policy"));
+            if current.policy.is_realtime() {
+                ui.label(format!("{} {}", current.policy.name(), current.rt_priority));
+            } else {
+                ui.label(current.policy.name());
+            }
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("policy")
+                    .selected_text(edit.policy.name())
+                    .show_ui(ui, |ui| {
+                        for policy in POLICIES {
+                            ui.selectable_value(&mut edit.policy, policy, policy.name());
+                        }
+                    });
+                if edit.policy.is_realtime() {
+                    edit.rt_priority = edit.rt_priority.max(1);
+                    ui.add(egui::DragValue::new(&mut edit.rt_priority).range(1..=99).prefix(tr("priority ")));
+                }
+                if ui.button(tr("Apply")).clicked() {
+                    result = Some(
+                        sched::set_policy(pid, edit.policy, edit.rt_priority)
+                            .map(|_| trf("Policy set to {}", &[&edit.policy.name()])),
+                    );
+                }
+            });
+            ui.end_row();
+
+            ui.strong(tr("Nice"));
+            ui.label(current.nice.to_string());
+            ui.horizontal(|ui| {
+                ui.add(egui::Slider::new(&mut edit.nice, -20..=19));
+                if ui.button(tr("Apply")).clicked() {
+                    result = Some(sched::set_nice(pid, edit.nice).map(|_| trf("Nice set to {}", &[&edit.nice])));
+                }
+            });
+            ui.end_row();
+
+            ui.strong(tr("I/O priority"));
+            ui.label(current.io.describe());
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("io_class")
+                    .selected_text(edit.io.class.name())
+                    .show_ui(ui, |ui| {
+                        for class in IO_CLASSES {
+                            ui.selectable_value(&mut edit.io.class, class, class.name());
+                        }
+                    });
+                ui.add(egui::DragValue::new(&mut edit.io.level).range(0..=7).prefix(tr("level ")));
+                if ui.button(tr("Apply")).clicked() {
+                    let io = edit.io;
+                    result = Some(
+                        sched::set_io_priority(pid, io)
+                            .map(|_| trf("I/O priority set to {}", &[&io.describe()])),
+                    );
+                }
+            });
+            ui.end_row();
+        });
+
+        if let Some(result) = result {
+            self.sched.report = Some(result.map_err(|e| e.to_string()));
+            self.sched.stale = true;
+        }
+
+        match &self.sched.report {
+            Some(Ok(done)) => {
+                ui.label(done);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            None => (),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use super::gatherer::ProcessInfo;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Reason {
+    Leak,
+    Runaway,
+    Both,
+}
+
+#[derive(Clone, Copy)]
+pub struct DetectorSettings {
+    // Number of consecutive samples a pattern must hold for.
+    pub window: usize,
+    // Percentage of the whole machine, like the normalized CPU column.
+    pub cpu_threshold: f32,
+}
+
+impl Default for DetectorSettings {
+    fn default() -> Self {
+        Self {
+            window: 60,
+            cpu_threshold: 50.0,
+        }
+    }
+}
+
+pub struct Suspect {
+    pub pid: u32,
+    pub name: String,
+    pub reason: Reason,
+    pub growth_per_sec: f64,
+    pub cpu_avg: f32,
+    pub memory: Vec<u64>,
+    pub cpu: Vec<f32>,
+}
+
+struct Track {
+    name: String,
+    memory: VecDeque<u64>,
+    cpu: VecDeque<f32>,
+    times: VecDeque<Instant>,
+}
+
+pub struct Detector {
+    pub settings: DetectorSettings,
+    tracks: HashMap<u32, Track>,
+}
+
+impl Detector {
+    pub fn new(settings: DetectorSettings) -> Self {
+        Self {
+            settings,
+            tracks: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, processes: &[&ProcessInfo], cores: usize) {
+        let now = Instant::now();
+        let window = self.settings.window.max(2);
+        let mut alive: HashMap<u32, Track> = HashMap::with_capacity(processes.len());
+
+        for process in processes {
+            let mut track = match self.tracks.remove(&process.pid) {
+                // A reused pid is a different program, start its history over.
+                Some(track) if track.name == process.name => track,
+                _ => Track {
+                    name: process.name.clone(),
+                    memory: VecDeque::with_capacity(window),
+                    cpu: VecDeque::with_capacity(window),
+                    times: VecDeque::with_capacity(window),
+                },
+            };
+
+            while track.memory.len() >= window {
+                track.memory.pop_front();
+                track.cpu.pop_front();
+                track.times.pop_front();
+            }
+            track.memory.push_back(process.memory);
+            track.cpu.push_back(process.cpu / cores.max(1) as f32);
+            track.times.push_back(now);
+
+            alive.insert(process.pid, track);
+        }
+
+        // Exited processes are dropped here with the old map.
+        self.tracks = alive;
+    }
+
+    pub fn suspects(&self) -> Vec<Suspect> {
+        let window = self.settings.window.max(2);
+        let mut suspects = Vec::new();
+
+        for (pid, track) in &self.tracks {
+            if track.memory.len() < window {
+                continue;
+            }
+
+            let growing = track
+                .memory
+                .iter()
+                .zip(track.memory.iter().skip(1))
+                .all(|(a, b)| a <= b)
+                && track.memory.back() > track.memory.front();
+            let spinning = track.cpu.iter().all(|c| *c >= self.settings.cpu_threshold);
+
+            let reason = match (growing, spinning) {
+                (true, true) => Reason::Both,
+                (true, false) => Reason::Leak,
+                (false, true) => Reason::Runaway,
+                (false, false) => continue,
+            };
+
+            let (Some(first), Some(last)) = (track.times.front(), track.times.back()) else {
+                continue;
+            };
+            let elapsed = last.duration_since(*first).as_secs_f64();
+            let grown = *track.memory.back().unwrap_or(&0) as f64
+                - *track.memory.front().unwrap_or(&0) as f64;
+
+            suspects.push(Suspect {
+                pid: *pid,
+                name: track.name.clone(),
+                reason,
+                growth_per_sec: if elapsed > 0.0 { grown / elapsed } else { 0.0 },
+                cpu_avg: track.cpu.iter().sum::<f32>() / track.cpu.len() as f32,
+                memory: track.memory.iter().copied().collect(),
+                cpu: track.cpu.iter().copied().collect(),
+            });
+        }
+
+        suspects.sort_by(|a, b| {
+            b.growth_per_sec
+                .total_cmp(&a.growth_per_sec)
+                .then(b.cpu_avg.total_cmp(&a.cpu_avg))
+        });
+        suspects
+    }
+}
@@ -1,31 +1,104 @@
-use std::collections::HashMap;
-use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, Users};
+use super::sensors::{SensorStats, Sensors};
+use std::collections::{HashMap, HashSet};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, ThreadKind, UpdateKind, Users};
 pub struct ProcessInfo {
     pub pid: u32,
     pub parent_pid: u32,
     pub name: String,
     pub cpu: f32,
-    pub memory: f64,
+    pub memory: u64,
     pub exe: String,
     pub user: String,
     pub child: Vec<ProcessInfo>,
+    pub linux: Option<LinuxInfo>,
+}
+
+// Only filled in by the /proc backend.
+pub struct LinuxInfo {
+    pub state: char,
+    pub threads: u32,
+    pub cmdline: String,
+    pub virtual_mem: u64,
+    pub minor_faults: u64,
+    pub major_faults: u64,
+    // /proc/<pid>/io is only readable for our own processes unless running as root.
+    pub read_bytes: Option<u64>,
+    pub write_bytes: Option<u64>,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Backend {
+    Sysinfo,
+    Proc,
+}
+
+// The /proc backend needs Linux, sysinfo works everywhere.
+pub const BACKENDS: &[Backend] = if cfg!(target_os = "linux") {
+    &[Backend::Sysinfo, Backend::Proc]
+} else {
+    &[Backend::Sysinfo]
+};
+
+impl Backend {
+    pub fn key(self) -> &'static str {
+        match self {
+            Backend::Sysinfo => "sysinfo",
+            Backend::Proc => "proc",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Backend> {
+        BACKENDS.iter().copied().find(|b| b.key() == key)
+    }
 }
 
 pub struct SysStats {
     pub processes: Vec<ProcessInfo>,
     pub cpu: f32,
-    pub mem: f64,
+    pub used_mem: u64,
+    pub total_mem: u64,
+    pub cores: usize,
+    pub sensors: SensorStats,
 }
 
 pub struct Monitor {
     sys: System,
     users: Users,
+    sensors: Sensors,
 }
 
 pub trait InfoGetter {
     fn new() -> Self;
     fn system_info_update(&mut self) -> SysStats;
-    fn tree(pid: u32, fam: &mut HashMap<u32, Vec<ProcessInfo>>) -> Vec<ProcessInfo>;
+}
+
+fn tree(pid: u32, fam: &mut HashMap<u32, Vec<ProcessInfo>>) -> Vec<ProcessInfo> {
+    let mut kids = fam.remove(&pid).unwrap_or_default();
+
+    for kid in &mut kids {
+        kid.child = tree(kid.pid, fam);
+    }
+
+    kids
+}
+
+// Nests processes grouped by parent pid. A process whose parent isn't in the
+// list (it exited, or we can't see it) becomes a root with its subtree intact.
+pub fn build_tree(mut families: HashMap<u32, Vec<ProcessInfo>>) -> Vec<ProcessInfo> {
+    let known: HashSet<u32> = families.values().flatten().map(|p| p.pid).collect();
+    let mut orphaned: Vec<u32> = families.keys().copied().filter(|ppid| !known.contains(ppid)).collect();
+    orphaned.sort();
+
+    let mut roots = Vec::new();
+    for ppid in orphaned {
+        roots.append(&mut tree(ppid, &mut families));
+    }
+    // Only a parent cycle can be left over, which /proc never reports.
+    for (_, mut rest) in families {
+        roots.append(&mut rest);
+    }
+
+    roots
 }
 
 impl InfoGetter for Monitor {
@@ -43,7 +116,11 @@ impl InfoGetter for Monitor {
 
         let users = Users::new_with_refreshed_list();
 
-        Self { sys, users }
+        Self {
+            sys,
+            users,
+            sensors: Sensors::new(),
+        }
     }
 
     fn system_info_update(&mut self) -> SysStats {
@@ -60,15 +137,21 @@ impl InfoGetter for Monitor {
         let mut families: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
 
         for (pid, process) in self.sys.processes() {
+            // sysinfo lists every thread as a process of its own, /proc doesn't.
+            if process.thread_kind() == Some(ThreadKind::Userland) {
+                continue;
+            }
+
             let mut info = ProcessInfo {
                 pid: pid.as_u32(),
                 parent_pid: 0u32,
                 name: String::new(),
-                cpu: process.cpu_usage() / (self.sys.cpus().len() as f32),
-                memory: (process.memory() as f64) / 1024.0 / 1024.0,
+                cpu: process.cpu_usage(),
+                memory: process.memory(),
                 exe: String::new(),
                 user: "Unknown".to_string(),
                 child: Vec::new(),
+                linux: None,
             };
 
             info.name = if let Some(correct_name) = process.name().to_str() {
@@ -102,33 +185,56 @@ impl InfoGetter for Monitor {
             families.entry(info.parent_pid).or_default().push(info);
         }
 
-        let mut process_info: Vec<ProcessInfo> = Self::tree(0, &mut families);
-
-        for (_, mut processe) in families {
-            for process in &mut processe {
-                process.child = Self::tree(process.pid, &mut HashMap::new());
-            }
-            process_info.append(&mut processe);
-        }
+        let process_info = build_tree(families);
 
         self.sys.refresh_cpu_all();
         let cpu = self.sys.global_cpu_usage();
-        let mem = (self.sys.used_memory() as f64 / self.sys.total_memory() as f64) * 100.0;
+        let used_mem = self.sys.used_memory();
+        let total_mem = self.sys.total_memory();
 
         SysStats {
             processes: process_info,
             cpu,
-            mem,
+            used_mem,
+            total_mem,
+            cores: self.sys.cpus().len(),
+            sensors: self.sensors.update(),
         }
     }
+}
 
-    fn tree(pid: u32, fam: &mut HashMap<u32, Vec<ProcessInfo>>) -> Vec<ProcessInfo> {
-        let mut kids = fam.remove(&pid).unwrap_or_default();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent_pid: u32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            parent_pid,
+            name: format!("p{}", pid),
+            cpu: 0.0,
+            memory: 0,
+            exe: String::new(),
+            user: String::new(),
+            child: Vec::new(),
+            linux: None,
+        }
+    }
 
-        for kid in &mut kids {
-            kid.child = Self::tree(kid.pid, fam);
+    #[test]
+    fn keeps_orphans_with_their_subtrees() {
+        let mut families: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
+        for (pid, parent_pid) in [(1, 0), (2, 1), (3, 2), (10, 99), (11, 10), (12, 11)] {
+            families.entry(parent_pid).or_default().push(process(pid, parent_pid));
         }
 
-        kids
+        let roots = build_tree(families);
+        let pids: Vec<u32> = roots.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, [1, 10]);
+
+        assert_eq!(roots[0].child[0].pid, 2);
+        assert_eq!(roots[0].child[0].child[0].pid, 3);
+        assert_eq!(roots[1].child[0].pid, 11);
+        assert_eq!(roots[1].child[0].child[0].pid, 12);
     }
 }
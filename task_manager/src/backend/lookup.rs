@@ -0,0 +1,315 @@
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+pub struct Holder {
+    pub pid: u32,
+    pub name: String,
+    pub fd: u32,
+    pub what: String,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+}
+
+pub struct SocketEntry {
+    pub protocol: Protocol,
+    pub local: (IpAddr, u16),
+    pub remote: (IpAddr, u16),
+    pub state: u8,
+    pub inode: u64,
+}
+
+impl Protocol {
+    fn table(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "/proc/net/tcp",
+            Protocol::Tcp6 => "/proc/net/tcp6",
+            Protocol::Udp => "/proc/net/udp",
+            Protocol::Udp6 => "/proc/net/udp6",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+        }
+    }
+}
+
+impl SocketEntry {
+    pub fn state_name(&self) -> &'static str {
+        match (self.protocol, self.state) {
+            (Protocol::Udp | Protocol::Udp6, 0x07) => "UNCONN",
+            (_, 0x01) => "ESTABLISHED",
+            (_, 0x02) => "SYN_SENT",
+            (_, 0x03) => "SYN_RECV",
+            (_, 0x04) => "FIN_WAIT1",
+            (_, 0x05) => "FIN_WAIT2",
+            (_, 0x06) => "TIME_WAIT",
+            (_, 0x07) => "CLOSE",
+            (_, 0x08) => "CLOSE_WAIT",
+            (_, 0x09) => "LAST_ACK",
+            (_, 0x0A) => "LISTEN",
+            (_, 0x0B) => "CLOSING",
+            _ => "UNKNOWN",
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} {} -> {} {}",
+            self.protocol.name(),
+            format_addr(self.local),
+            format_addr(self.remote),
+            self.state_name()
+        )
+    }
+}
+
+fn format_addr((ip, port): (IpAddr, u16)) -> String {
+    match ip {
+        IpAddr::V4(v4) => format!("{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("[{}]:{}", v6, port),
+    }
+}
+
+// The kernel prints each 32-bit word of the address in host byte order.
+fn parse_addr(field: &str) -> Option<(IpAddr, u16)> {
+    let (ip_hex, port_hex) = field.split_once(':')?;
+    let port = u16::from_str_radix(port_hex, 16).ok()?;
+    if ip_hex.len() != 8 && ip_hex.len() != 32 {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = Vec::new();
+    for chunk in ip_hex.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&bytes);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+
+    Some((ip, port))
+}
+
+pub fn parse_socket_table(protocol: Protocol, contents: &str) -> Vec<SocketEntry> {
+    let mut entries = Vec::new();
+
+    for line in contents.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 {
+            continue;
+        }
+
+        let (Some(local), Some(remote)) = (parse_addr(fields[1]), parse_addr(fields[2])) else {
+            continue;
+        };
+        let Ok(state) = u8::from_str_radix(fields[3], 16) else {
+            continue;
+        };
+        let Ok(inode) = fields[9].parse::<u64>() else {
+            continue;
+        };
+
+        entries.push(SocketEntry {
+            protocol,
+            local,
+            remote,
+            state,
+            inode,
+        });
+    }
+
+    entries
+}
+
+pub fn sockets() -> Vec<SocketEntry> {
+    let mut all = Vec::new();
+
+    for protocol in [Protocol::Tcp, Protocol::Tcp6, Protocol::Udp, Protocol::Udp6] {
+        if let Ok(contents) = fs::read_to_string(protocol.table()) {
+            all.append(&mut parse_socket_table(protocol, &contents));
+        }
+    }
+
+    all
+}
+
+fn process_name(pid: u32) -> String {
+    match fs::read_to_string(format!("/proc/{}/comm", pid)) {
+        Ok(name) => name.trim_end().to_string(),
+        Err(_) => "Unknown".to_string(),
+    }
+}
+
+// Calls `visit` with (pid, fd, link target) for every descriptor we are allowed to read.
+// Processes owned by other users are skipped silently unless we run as root.
+fn for_each_fd(mut visit: impl FnMut(u32, u32, &Path)) -> io::Result<()> {
+    for entry in fs::read_dir("/proc")? {
+        let Ok(entry) = entry else { continue };
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        for fd in fds.flatten() {
+            let Some(fd_num) = fd.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+                continue;
+            };
+            if let Ok(target) = fs::read_link(fd.path()) {
+                visit(pid, fd_num, &target);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn who_has_file(path: &Path) -> io::Result<Vec<Holder>> {
+    super::linux_only("looking up open files")?;
+    let wanted: PathBuf = fs::canonicalize(path)?;
+    let is_dir = wanted.is_dir();
+    let mut holders = Vec::new();
+
+    for_each_fd(|pid, fd, target| {
+        let matches = target == wanted || (is_dir && target.starts_with(&wanted));
+        if matches {
+            holders.push(Holder {
+                pid,
+                name: process_name(pid),
+                fd,
+                what: target.display().to_string(),
+            });
+        }
+    })?;
+
+    Ok(holders)
+}
+
+pub fn who_uses_port(port: u16) -> io::Result<Vec<Holder>> {
+    super::linux_only("looking up ports")?;
+    let matching: Vec<SocketEntry> = sockets()
+        .into_iter()
+        .filter(|entry| entry.local.1 == port)
+        .collect();
+    let mut holders = Vec::new();
+
+    if matching.is_empty() {
+        return Ok(holders);
+    }
+
+    for_each_fd(|pid, fd, target| {
+        let Some(inode) = target
+            .to_str()
+            .and_then(|t| t.strip_prefix("socket:["))
+            .and_then(|t| t.strip_suffix(']'))
+            .and_then(|t| t.parse::<u64>().ok())
+        else {
+            return;
+        };
+
+        for entry in matching.iter().filter(|entry| entry.inode == inode) {
+            holders.push(Holder {
+                pid,
+                name: process_name(pid),
+                fd,
+                what: entry.describe(),
+            });
+        }
+    })?;
+
+    Ok(holders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP6: &str = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 12345 1 0000000000000000 100 0 0 10 0
+   1: 00000000000000000000000001000000:0277 00000000000000000000000001000000:D2F0 01 00000000:00000000 00:00000000 00000000     0        0 67890 1 0000000000000000 20 4 30 10 -1
+   2: 0000000000000000FFFF00000100007F:0050 0000000000000000FFFF00000100007F:C350 06 00000000:00000000 03:00000d6a 00000000     0        0 0 3 0000000000000000
+   3: garbage line
+";
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn parses_addresses() {
+        assert_eq!(parse_addr("0100007F:0050"), Some((IpAddr::V4(Ipv4Addr::LOCALHOST), 80)));
+        assert_eq!(
+            parse_addr("00000000000000000000000001000000:0277"),
+            Some((IpAddr::V6(Ipv6Addr::LOCALHOST), 631))
+        );
+        assert_eq!(
+            parse_addr("0000000000000000FFFF00000100007F:0050"),
+            Some((IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped()), 80))
+        );
+        assert_eq!(parse_addr("0100007F"), None);
+        assert_eq!(parse_addr("01007F:0050"), None);
+        assert_eq!(parse_addr("0100007F:XYZ"), None);
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn parses_socket_table() {
+        let entries = parse_socket_table(Protocol::Tcp6, TCP6);
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].local, (IpAddr::V6(Ipv6Addr::UNSPECIFIED), 8080));
+        assert_eq!(entries[0].state_name(), "LISTEN");
+        assert_eq!(entries[0].inode, 12345);
+
+        assert_eq!(entries[1].remote, (IpAddr::V6(Ipv6Addr::LOCALHOST), 54000));
+        assert_eq!(entries[1].state_name(), "ESTABLISHED");
+        assert_eq!(entries[1].describe(), "tcp6 [::1]:631 -> [::1]:54000 ESTABLISHED");
+
+        assert_eq!(entries[2].state_name(), "TIME_WAIT");
+        assert_eq!(entries[2].inode, 0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn finds_own_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let holders = who_uses_port(port).unwrap();
+        assert!(holders.iter().any(|h| h.pid == std::process::id()));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn finds_own_file() {
+        let path = std::env::temp_dir().join(format!("task_manager_lookup_{}", std::process::id()));
+        let file = fs::File::create(&path).unwrap();
+
+        let holders = who_has_file(&path);
+        drop(file);
+        let _ = fs::remove_file(&path);
+
+        let holders = holders.unwrap();
+        assert!(holders.iter().any(|h| h.pid == std::process::id()));
+    }
+}
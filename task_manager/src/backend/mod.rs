@@ -1 +1,22 @@
+pub mod detector;
 pub mod gatherer;
+pub mod lookup;
+pub mod procfs;
+pub mod sched;
+pub mod sensors;
+pub mod services;
+pub mod signals;
+pub mod snapshot;
+pub mod threads;
+
+use std::io;
+
+// lookup, procfs, threads, services and signals read /proc and sched makes Linux-only
+// syscalls. Elsewhere they report Unsupported (or nothing) instead of failing to build.
+pub fn linux_only(what: &str) -> io::Result<()> {
+    if cfg!(target_os = "linux") {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::Unsupported, format!("{what} is only available on Linux")))
+    }
+}
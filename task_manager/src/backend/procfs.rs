@@ -0,0 +1,343 @@
+use super::gatherer::{build_tree, InfoGetter, LinuxInfo, ProcessInfo, SysStats};
+use super::sensors::Sensors;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+// /proc reports times in USER_HZ. It is 100 on nearly every system, but the kernel
+// only promises what sysconf says, so that is asked once and 100 is the fallback.
+pub(crate) fn ticks_per_second() -> f64 {
+    static TICKS: OnceLock<f64> = OnceLock::new();
+    *TICKS.get_or_init(|| {
+        #[cfg(target_os = "linux")]
+        {
+            let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+            if ticks > 0 {
+                return ticks as f64;
+            }
+        }
+        100.0
+    })
+}
+
+// The kernel truncates comm to 15 bytes, longer names are recovered from the command line.
+const COMM_LEN: usize = 15;
+
+// The fields of /proc/<pid>/stat we use, the same layout serves /proc/<pid>/task/<tid>/stat.
+pub(crate) struct Stat {
+    pub name: String,
+    pub state: char,
+    pub parent_pid: u32,
+    pub pgrp: u32,
+    pub session: u32,
+    pub ticks: u64,
+    pub minor_faults: u64,
+    pub major_faults: u64,
+    pub threads: u32,
+    pub start_time: u64,
+    pub virtual_mem: u64,
+    pub processor: Option<u32>,
+}
+
+// Reads everything straight from /proc instead of going through sysinfo, which
+// also makes the Linux-only fields in `LinuxInfo` available.
+pub struct ProcMonitor {
+    // Keyed by pid, the start time tells a reused pid apart from the old process.
+    last: HashMap<u32, (u64, u64)>,
+    at: Instant,
+    cpu_last: (u64, u64),
+    users: HashMap<u32, String>,
+    sensors: Sensors,
+}
+
+// The name sits in parentheses and may itself contain spaces or ')'.
+pub(crate) fn parse_stat(contents: &str) -> Option<Stat> {
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let name = contents[open + 1..close].to_string();
+    let fields: Vec<&str> = contents[close + 1..].split_whitespace().collect();
+    let field = |i: usize| fields.get(i).and_then(|f| f.parse::<u64>().ok());
+
+    Some(Stat {
+        name,
+        state: fields.first()?.chars().next()?,
+        parent_pid: field(1)? as u32,
+        pgrp: field(2)? as u32,
+        session: field(3)? as u32,
+        minor_faults: field(7)?,
+        major_faults: field(9)?,
+        ticks: field(11)? + field(12)?,
+        threads: field(17)? as u32,
+        start_time: field(19)?,
+        virtual_mem: field(20)?,
+        processor: field(36).map(|p| p as u32),
+    })
+}
+
+fn status_value<'a>(status: &'a str, key: &str) -> Option<&'a str> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(str::trim)
+}
+
+// Values in /proc/meminfo and /proc/<pid>/status are in kB.
+fn kilobytes(value: Option<&str>) -> u64 {
+    value
+        .and_then(|v| v.trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map_or(0, |kb| kb * 1024)
+}
+
+fn io_bytes(pid: u32) -> (Option<u64>, Option<u64>) {
+    // Only readable for our own processes unless running as root.
+    let Ok(io) = fs::read_to_string(format!("/proc/{}/io", pid)) else {
+        return (None, None);
+    };
+    let value = |key: &str| status_value(&io, key).and_then(|v| v.parse::<u64>().ok());
+
+    (value("read_bytes"), value("write_bytes"))
+}
+
+fn users() -> HashMap<u32, String> {
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+
+    passwd
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split(':');
+            let name = parts.next()?;
+            let uid = parts.nth(1)?.parse::<u32>().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+// Returns (busy, total) jiffies over all cpus and the number of cpus.
+fn cpu_times() -> ((u64, u64), usize) {
+    let stat = fs::read_to_string("/proc/stat").unwrap_or_default();
+    let mut times = (0, 0);
+    let mut cores = 0;
+
+    for line in stat.lines() {
+        if line.starts_with("cpu ") {
+            let values: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            // idle and iowait are the 4th and 5th columns, guest time is already part of user.
+            let total: u64 = values.iter().take(8).sum();
+            let idle = values.get(3).unwrap_or(&0) + values.get(4).unwrap_or(&0);
+            times = (total - idle, total);
+        } else if line.starts_with("cpu") {
+            cores += 1;
+        }
+    }
+
+    (times, cores)
+}
+
+impl ProcMonitor {
+    fn process(&self, pid: u32, elapsed: f64) -> Option<(ProcessInfo, (u64, u64))> {
+        let dir = format!("/proc/{}", pid);
+        let stat = parse_stat(&fs::read_to_string(format!("{}/stat", dir)).ok()?)?;
+        let status = fs::read_to_string(format!("{}/status", dir)).unwrap_or_default();
+
+        let cmdline: Vec<String> = fs::read(format!("{}/cmdline", dir))
+            .unwrap_or_default()
+            .split(|b| *b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect();
+
+        let name = match cmdline.first().and_then(|arg| arg.rsplit('/').next()) {
+            Some(full) if stat.name.len() == COMM_LEN && full.starts_with(&stat.name) => full.to_string(),
+            _ => stat.name.clone(),
+        };
+
+        let cpu = match self.last.get(&pid) {
+            Some((start, ticks)) if *start == stat.start_time && elapsed > 0.0 => {
+                stat.ticks.saturating_sub(*ticks) as f64 / ticks_per_second() / elapsed * 100.0
+            }
+            _ => 0.0,
+        };
+
+        let exe = fs::read_link(format!("{}/exe", dir))
+            .ok()
+            .and_then(|path| path.to_str().map(|p| p.to_string()))
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let user = status_value(&status, "Uid")
+            .and_then(|ids| ids.split_whitespace().next())
+            .and_then(|uid| uid.parse::<u32>().ok())
+            .and_then(|uid| self.users.get(&uid).cloned())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let (read_bytes, write_bytes) = io_bytes(pid);
+
+        let info = ProcessInfo {
+            pid,
+            parent_pid: stat.parent_pid,
+            name,
+            cpu: cpu as f32,
+            memory: kilobytes(status_value(&status, "VmRSS")),
+            exe,
+            user,
+            child: Vec::new(),
+            linux: Some(LinuxInfo {
+                state: stat.state,
+                threads: stat.threads,
+                cmdline: cmdline.join(" "),
+                virtual_mem: stat.virtual_mem,
+                minor_faults: stat.minor_faults,
+                major_faults: stat.major_faults,
+                read_bytes,
+                write_bytes,
+            }),
+        };
+
+        Some((info, (stat.start_time, stat.ticks)))
+    }
+}
+
+impl InfoGetter for ProcMonitor {
+    fn new() -> Self {
+        let mut monitor = Self {
+            last: HashMap::new(),
+            at: Instant::now(),
+            cpu_last: (0, 0),
+            users: users(),
+            sensors: Sensors::new(),
+        };
+
+        // Prime the tick counters so the first real update already has usage figures.
+        monitor.system_info_update();
+        monitor
+    }
+
+    fn system_info_update(&mut self) -> SysStats {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.at).as_secs_f64();
+        let mut last = HashMap::with_capacity(self.last.len());
+        let mut families: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
+
+        let pids = fs::read_dir("/proc")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok());
+
+        for pid in pids {
+            // The process may exit between listing /proc and reading its files.
+            let Some((info, times)) = self.process(pid, elapsed) else {
+                continue;
+            };
+            last.insert(pid, times);
+            families.entry(info.parent_pid).or_default().push(info);
+        }
+
+        self.last = last;
+        self.at = now;
+
+        let process_info = build_tree(families);
+
+        let (times, cores) = cpu_times();
+        let busy = times.0.saturating_sub(self.cpu_last.0);
+        let total = times.1.saturating_sub(self.cpu_last.1);
+        self.cpu_last = times;
+        let cpu = if total == 0 { 0.0 } else { busy as f32 / total as f32 * 100.0 };
+
+        let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+        let total_mem = kilobytes(status_value(&meminfo, "MemTotal"));
+        let available = kilobytes(status_value(&meminfo, "MemAvailable"));
+
+        SysStats {
+            processes: process_info,
+            cpu,
+            used_mem: total_mem.saturating_sub(available),
+            total_mem,
+            cores,
+            sensors: self.sensors.update(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stat() {
+        let line = "1234 (tmux: server) S 1 1234 1234 0 -1 4194560 5008 0 3 0 120 45 0 0 20 0 \
+                    2 0 7654321 12345678 1024 18446744073709551615 1 1 0 0 0 0 0 4096 134433281 \
+                    0 0 0 17 3 0 0 0 0 0";
+        let stat = parse_stat(line).unwrap();
+        assert_eq!(stat.name, "tmux: server");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.parent_pid, 1);
+        assert_eq!((stat.pgrp, stat.session), (1234, 1234));
+        assert_eq!(stat.minor_faults, 5008);
+        assert_eq!(stat.major_faults, 3);
+        assert_eq!(stat.ticks, 165);
+        assert_eq!(stat.threads, 2);
+        assert_eq!(stat.start_time, 7654321);
+        assert_eq!(stat.virtual_mem, 12345678);
+        assert_eq!(stat.processor, Some(3));
+
+        assert_eq!(parse_stat("1 (a) b) R 0").map(|s| s.name), None);
+        assert!(parse_stat("1 (a) b) R 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0").is_some_and(|s| s.name == "a) b"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn backends_agree() {
+        use crate::backend::gatherer::Monitor;
+        use std::collections::HashSet;
+        use std::process::Command;
+
+        fn flatten(processes: &[ProcessInfo], out: &mut HashMap<u32, (u32, String)>) {
+            for process in processes {
+                out.insert(process.pid, (process.parent_pid, process.name.clone()));
+                flatten(&process.child, out);
+            }
+        }
+
+        fn sample<G: InfoGetter>() -> HashMap<u32, (u32, String)> {
+            let mut pids = HashMap::new();
+            flatten(&G::new().system_info_update().processes, &mut pids);
+            pids
+        }
+
+        fn listed() -> HashSet<u32> {
+            fs::read_dir("/proc")
+                .unwrap()
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+                .collect()
+        }
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let own = child.id();
+
+        let before = listed();
+        let sysinfo = sample::<Monitor>();
+        let proc = sample::<ProcMonitor>();
+        let after = listed();
+        let _ = child.kill();
+        let _ = child.wait();
+
+        // Only processes that lived through both samples have to be in both.
+        for pid in before.intersection(&after) {
+            assert!(sysinfo.contains_key(pid), "sysinfo is missing {}", pid);
+            assert!(proc.contains_key(pid), "/proc is missing {}", pid);
+        }
+        // Threads never show up in the /proc listing, so neither backend may report them.
+        for pid in sysinfo.keys().chain(proc.keys()) {
+            assert!(before.contains(pid) || after.contains(pid), "{} is not a process", pid);
+        }
+
+        assert_eq!(sysinfo.get(&own), proc.get(&own));
+        assert_eq!(proc[&own], (std::process::id(), "sleep".to_string()));
+    }
+}
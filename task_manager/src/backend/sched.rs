@@ -0,0 +1,182 @@
+use std::io;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Policy {
+    Other,
+    Fifo,
+    RoundRobin,
+    Batch,
+    Idle,
+}
+
+pub const POLICIES: [Policy; 5] = [
+    Policy::Other,
+    Policy::Fifo,
+    Policy::RoundRobin,
+    Policy::Batch,
+    Policy::Idle,
+];
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum IoClass {
+    None,
+    RealTime,
+    BestEffort,
+    Idle,
+}
+
+pub const IO_CLASSES: [IoClass; 4] = [
+    IoClass::None,
+    IoClass::RealTime,
+    IoClass::BestEffort,
+    IoClass::Idle,
+];
+
+#[derive(PartialEq, Clone, Copy)]
+pub struct IoPriority {
+    pub class: IoClass,
+    // 0 is the highest priority and 7 the lowest, ignored for the idle class.
+    pub level: u8,
+}
+
+#[derive(Clone)]
+pub struct Scheduling {
+    pub affinity: Vec<usize>,
+    pub policy: Policy,
+    // Static priority for FIFO and round robin, always 0 for the other policies.
+    pub rt_priority: i32,
+    pub nice: i32,
+    pub io: IoPriority,
+}
+
+impl Policy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Policy::Other => "SCHED_OTHER",
+            Policy::Fifo => "SCHED_FIFO",
+            Policy::RoundRobin => "SCHED_RR",
+            Policy::Batch => "SCHED_BATCH",
+            Policy::Idle => "SCHED_IDLE",
+        }
+    }
+
+    pub fn is_realtime(self) -> bool {
+        matches!(self, Policy::Fifo | Policy::RoundRobin)
+    }
+}
+
+impl IoClass {
+    pub fn name(self) -> &'static str {
+        match self {
+            IoClass::None => "none",
+            IoClass::RealTime => "realtime",
+            IoClass::BestEffort => "best-effort",
+            IoClass::Idle => "idle",
+        }
+    }
+}
+
+impl IoPriority {
+    pub fn describe(&self) -> String {
+        match self.class {
+            IoClass::Idle => self.class.name().to_string(),
+            // Without a class the kernel derives the level from the nice value.
+            IoClass::None => format!("{} (from nice)", self.class.name()),
+            _ => format!("{} {}", self.class.name(), self.level),
+        }
+    }
+}
+
+// Formats a cpu list the way taskset and /proc/<pid>/status do, e.g. `0-3,6`.
+pub fn cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut i = 0;
+
+    while i < cpus.len() {
+        let start = cpus[i];
+        while i + 1 < cpus.len() && cpus[i + 1] == cpus[i] + 1 {
+            i += 1;
+        }
+        if cpus[i] == start {
+            ranges.push(start.to_string());
+        } else {
+            ranges.push(format!("{}-{}", start, cpus[i]));
+        }
+        i += 1;
+    }
+
+    ranges.join(",")
+}
+
+// The calls behind these are Linux-only, elsewhere every one of them reports Unsupported.
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::{affinity, io_priority, nice, policy, set_affinity, set_io_priority, set_nice, set_policy};
+
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use super::{IoPriority, Policy};
+    use std::io;
+
+    fn unsupported<T>() -> io::Result<T> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "scheduling controls are only available on Linux"))
+    }
+
+    pub fn affinity(_pid: u32) -> io::Result<Vec<usize>> {
+        unsupported()
+    }
+
+    pub fn set_affinity(_pid: u32, _cpus: &[usize]) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn policy(_pid: u32) -> io::Result<(Policy, i32)> {
+        unsupported()
+    }
+
+    pub fn set_policy(_pid: u32, _policy: Policy, _rt_priority: i32) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn nice(_pid: u32) -> io::Result<i32> {
+        unsupported()
+    }
+
+    pub fn set_nice(_pid: u32, _value: i32) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn io_priority(_pid: u32) -> io::Result<IoPriority> {
+        unsupported()
+    }
+
+    pub fn set_io_priority(_pid: u32, _prio: IoPriority) -> io::Result<()> {
+        unsupported()
+    }
+}
+#[cfg(not(target_os = "linux"))]
+pub use unsupported::*;
+
+pub fn scheduling(pid: u32) -> io::Result<Scheduling> {
+    let (policy, rt_priority) = policy(pid)?;
+    Ok(Scheduling {
+        affinity: affinity(pid)?,
+        policy,
+        rt_priority,
+        nice: nice(pid)?,
+        io: io_priority(pid)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_cpu_lists() {
+        assert_eq!(cpu_list(&[]), "");
+        assert_eq!(cpu_list(&[0, 1, 2, 3]), "0-3");
+        assert_eq!(cpu_list(&[0, 2, 3, 4, 7]), "0,2-4,7");
+    }
+}
@@ -0,0 +1,359 @@
+use super::{IoClass, IoPriority, Policy, IO_CLASSES, POLICIES};
+use std::fs;
+use std::io;
+use std::mem;
+
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+const IOPRIO_PRIO_MASK: libc::c_int = (1 << IOPRIO_CLASS_SHIFT) - 1;
+
+impl Policy {
+    fn raw(self) -> libc::c_int {
+        match self {
+            Policy::Other => libc::SCHED_OTHER,
+            Policy::Fifo => libc::SCHED_FIFO,
+            Policy::RoundRobin => libc::SCHED_RR,
+            Policy::Batch => libc::SCHED_BATCH,
+            Policy::Idle => libc::SCHED_IDLE,
+        }
+    }
+
+    fn from_raw(raw: libc::c_int) -> Option<Policy> {
+        // The reset-on-fork flag is reported along with the policy.
+        let raw = raw & !libc::SCHED_RESET_ON_FORK;
+        POLICIES.into_iter().find(|p| p.raw() == raw)
+    }
+}
+
+impl IoClass {
+    fn raw(self) -> libc::c_int {
+        match self {
+            IoClass::None => 0,
+            IoClass::RealTime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        }
+    }
+}
+
+fn to_pid(pid: u32) -> io::Result<libc::pid_t> {
+    // 0 means the calling process for every call here, never allow it.
+    match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => Ok(pid),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid pid")),
+    }
+}
+
+fn check(res: libc::c_int) -> io::Result<libc::c_int> {
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
+
+fn denied(why: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, why.to_string())
+}
+
+fn privileged() -> bool {
+    // SAFETY: geteuid has no arguments and cannot fail.
+    unsafe { libc::geteuid() == 0 }
+}
+
+// The kernel has the final say, these checks only turn the common refusals into
+// a readable reason before anything is attempted.
+fn check_owner(pid: u32) -> io::Result<()> {
+    if privileged() {
+        return Ok(());
+    }
+
+    let status = fs::read_to_string(format!("/proc/{}/status", pid))?;
+    let uid = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().next())
+        .and_then(|uid| uid.parse::<u32>().ok());
+
+    // SAFETY: geteuid has no arguments and cannot fail.
+    let own = unsafe { libc::geteuid() };
+    match uid {
+        Some(uid) if uid == own => Ok(()),
+        _ => Err(denied("the process belongs to another user")),
+    }
+}
+
+pub fn affinity(pid: u32) -> io::Result<Vec<usize>> {
+    let raw = to_pid(pid)?;
+
+    // SAFETY: cpu_set_t is plain data and sched_getaffinity writes at most its size.
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        check(libc::sched_getaffinity(raw, mem::size_of::<libc::cpu_set_t>(), &mut set))?;
+        Ok((0..libc::CPU_SETSIZE as usize)
+            .filter(|cpu| libc::CPU_ISSET(*cpu, &set))
+            .collect())
+    }
+}
+
+pub fn set_affinity(pid: u32, cpus: &[usize]) -> io::Result<()> {
+    let raw = to_pid(pid)?;
+    if cpus.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "at least one CPU is required"));
+    }
+    check_owner(pid)?;
+
+    // SAFETY: cpu_set_t is plain data and every index is checked against CPU_SETSIZE.
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        for cpu in cpus {
+            if *cpu >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "CPU index out of range"));
+            }
+            libc::CPU_SET(*cpu, &mut set);
+        }
+        check(libc::sched_setaffinity(raw, mem::size_of::<libc::cpu_set_t>(), &set))?;
+    }
+    Ok(())
+}
+
+pub fn policy(pid: u32) -> io::Result<(Policy, i32)> {
+    let raw = to_pid(pid)?;
+
+    // SAFETY: sched_param is plain data filled in by the kernel.
+    let (policy, param) = unsafe {
+        let policy = check(libc::sched_getscheduler(raw))?;
+        let mut param: libc::sched_param = mem::zeroed();
+        check(libc::sched_getparam(raw, &mut param))?;
+        (policy, param)
+    };
+
+    let policy = Policy::from_raw(policy)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "unknown scheduling policy"))?;
+    Ok((policy, param.sched_priority))
+}
+
+pub fn set_policy(pid: u32, policy: Policy, rt_priority: i32) -> io::Result<()> {
+    let raw = to_pid(pid)?;
+    check_owner(pid)?;
+
+    let priority = if policy.is_realtime() {
+        // SAFETY: both calls only take the policy constant.
+        let (min, max) = unsafe {
+            (
+                libc::sched_get_priority_min(policy.raw()),
+                libc::sched_get_priority_max(policy.raw()),
+            )
+        };
+        if !(min..=max).contains(&rt_priority) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} priority must be between {} and {}", policy.name(), min, max),
+            ));
+        }
+        rt_priority
+    } else {
+        0
+    };
+
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // SAFETY: param lives across the call and is only read by the kernel.
+    match check(unsafe { libc::sched_setscheduler(raw, policy.raw(), &param) }) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied && policy.is_realtime() => {
+            Err(denied("real-time policies need CAP_SYS_NICE or an RLIMIT_RTPRIO allowance"))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub fn nice(pid: u32) -> io::Result<i32> {
+    let raw = to_pid(pid)?;
+
+    // -1 is a valid niceness, so failure can only be told apart through errno.
+    // SAFETY: errno is thread local and getpriority only takes integers.
+    unsafe {
+        *libc::__errno_location() = 0;
+        let value = libc::getpriority(libc::PRIO_PROCESS, raw as libc::id_t);
+        if value == -1 && *libc::__errno_location() != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(value)
+    }
+}
+
+pub fn set_nice(pid: u32, value: i32) -> io::Result<()> {
+    let raw = to_pid(pid)?;
+    if !(-20..=19).contains(&value) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "nice must be between -20 and 19"));
+    }
+    check_owner(pid)?;
+
+    // How far below the current value we may go depends on RLIMIT_NICE and CAP_SYS_NICE,
+    // so the kernel decides and only its refusal is explained.
+    // SAFETY: setpriority only takes integers.
+    match check(unsafe { libc::setpriority(libc::PRIO_PROCESS, raw as libc::id_t, value) }) {
+        Ok(_) => Ok(()),
+        Err(e) if matches!(e.raw_os_error(), Some(libc::EPERM | libc::EACCES)) => {
+            Err(denied("raising the priority beyond RLIMIT_NICE needs CAP_SYS_NICE"))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub fn io_priority(pid: u32) -> io::Result<IoPriority> {
+    let raw = to_pid(pid)?;
+
+    // SAFETY: ioprio_get only takes integers.
+    let value = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, raw) } as libc::c_int;
+    let value = check(value)?;
+
+    let class = IO_CLASSES
+        .into_iter()
+        .find(|c| c.raw() == value >> IOPRIO_CLASS_SHIFT)
+        .unwrap_or(IoClass::None);
+    Ok(IoPriority {
+        class,
+        level: (value & IOPRIO_PRIO_MASK) as u8,
+    })
+}
+
+pub fn set_io_priority(pid: u32, prio: IoPriority) -> io::Result<()> {
+    let raw = to_pid(pid)?;
+    if prio.level > 7 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "I/O priority level must be between 0 and 7"));
+    }
+    check_owner(pid)?;
+    if prio.class == IoClass::RealTime && !privileged() {
+        return Err(denied("the realtime I/O class needs CAP_SYS_ADMIN"));
+    }
+
+    let level = if prio.class == IoClass::Idle { 0 } else { prio.level as libc::c_int };
+    let value = (prio.class.raw() << IOPRIO_CLASS_SHIFT) | level;
+    // SAFETY: ioprio_set only takes integers.
+    let res = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, raw, value) };
+    check(res as libc::c_int)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::scheduling;
+    use super::*;
+    use std::process::{Child, Command};
+
+    // A child of our own to change, killed even when an assertion fails.
+    struct Sleeper(Child);
+
+    impl Sleeper {
+        fn spawn() -> Sleeper {
+            Sleeper(Command::new("sleep").arg("30").spawn().unwrap())
+        }
+
+        fn pid(&self) -> u32 {
+            self.0.id()
+        }
+    }
+
+    impl Drop for Sleeper {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[test]
+    fn round_trips_affinity() {
+        let child = Sleeper::spawn();
+        let cpus = affinity(child.pid()).unwrap();
+        assert!(!cpus.is_empty());
+
+        set_affinity(child.pid(), &cpus[..1]).unwrap();
+        assert_eq!(affinity(child.pid()).unwrap(), &cpus[..1]);
+
+        assert!(set_affinity(child.pid(), &[]).is_err());
+        assert!(set_affinity(child.pid(), &[libc::CPU_SETSIZE as usize]).is_err());
+    }
+
+    #[test]
+    fn round_trips_nice() {
+        let child = Sleeper::spawn();
+        set_nice(child.pid(), 5).unwrap();
+        assert_eq!(nice(child.pid()).unwrap(), 5);
+        set_nice(child.pid(), 10).unwrap();
+        assert_eq!(nice(child.pid()).unwrap(), 10);
+
+        assert!(set_nice(child.pid(), 20).is_err());
+
+        // Going back down is up to RLIMIT_NICE (a floor of 20 - limit) unless we have CAP_SYS_NICE.
+        // SAFETY: rlimit is plain data filled in by the kernel.
+        let limit = unsafe {
+            let mut limit: libc::rlimit = mem::zeroed();
+            libc::getrlimit(libc::RLIMIT_NICE, &mut limit);
+            limit.rlim_cur
+        };
+        match set_nice(child.pid(), 0) {
+            Ok(()) => assert_eq!(nice(child.pid()).unwrap(), 0),
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+                assert!(!privileged() && limit < 20);
+            }
+        }
+        if limit >= 15 {
+            set_nice(child.pid(), 5).unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trips_policy() {
+        let child = Sleeper::spawn();
+        // Idle goes last, leaving it needs privileges or an RLIMIT_NICE allowance.
+        for policy in [Policy::Batch, Policy::Other, Policy::Idle] {
+            set_policy(child.pid(), policy, 0).unwrap();
+            let (current, rt_priority) = super::policy(child.pid()).unwrap();
+            assert!(current == policy, "expected {}, got {}", policy.name(), current.name());
+            assert_eq!(rt_priority, 0);
+        }
+
+        assert!(set_policy(child.pid(), Policy::Fifo, 0).is_err());
+    }
+
+    #[test]
+    fn round_trips_io_priority() {
+        let child = Sleeper::spawn();
+        let best_effort = IoPriority {
+            class: IoClass::BestEffort,
+            level: 5,
+        };
+        set_io_priority(child.pid(), best_effort).unwrap();
+        assert!(io_priority(child.pid()).unwrap() == best_effort);
+
+        set_io_priority(child.pid(), IoPriority { class: IoClass::Idle, level: 3 }).unwrap();
+        assert!(io_priority(child.pid()).unwrap().class == IoClass::Idle);
+
+        assert!(set_io_priority(child.pid(), IoPriority { class: IoClass::BestEffort, level: 8 }).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_pids() {
+        for pid in [0, u32::MAX] {
+            assert_eq!(affinity(pid).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(set_nice(pid, 10).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(set_policy(pid, Policy::Batch, 0).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+            assert!(scheduling(pid).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_processes_of_other_users() {
+        if privileged() {
+            return;
+        }
+        // init always belongs to root.
+        assert_eq!(set_nice(1, 19).unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(set_affinity(1, &[0]).unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(set_policy(1, Policy::Idle, 0).unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use sysinfo::Components;
+
+const HISTORY_LEN: usize = 60;
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+#[derive(Clone)]
+pub struct Reading {
+    pub label: String,
+    pub temperature: Option<f32>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub critical: Option<f32>,
+    pub history: VecDeque<f32>,
+}
+
+pub struct PowerSupply {
+    pub name: String,
+    pub kind: String,
+    pub status: Option<String>,
+    pub capacity: Option<u8>,
+    pub online: Option<bool>,
+    pub power_watts: Option<f64>,
+}
+
+#[derive(Default)]
+pub struct SensorStats {
+    pub readings: Vec<Reading>,
+    pub supplies: Vec<PowerSupply>,
+}
+
+pub struct Sensors {
+    components: Components,
+    readings: HashMap<String, Reading>,
+}
+
+impl Sensors {
+    pub fn new() -> Self {
+        Self {
+            components: Components::new_with_refreshed_list(),
+            readings: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self) -> SensorStats {
+        self.components.refresh(true);
+
+        for component in self.components.list() {
+            let label = component.label().to_string();
+            let temperature = component.temperature().filter(|t| t.is_finite());
+
+            let reading = self.readings.entry(label.clone()).or_insert(Reading {
+                label,
+                temperature: None,
+                min: None,
+                max: None,
+                critical: None,
+                history: VecDeque::with_capacity(HISTORY_LEN),
+            });
+
+            reading.temperature = temperature;
+            reading.critical = component.critical().filter(|t| t.is_finite());
+
+            if let Some(t) = temperature {
+                reading.min = Some(reading.min.map_or(t, |m| m.min(t)));
+                reading.max = Some(reading.max.map_or(t, |m| m.max(t)));
+
+                if reading.history.len() == HISTORY_LEN {
+                    reading.history.pop_front();
+                }
+                reading.history.push_back(t);
+            }
+
+            // sysinfo remembers the highest value it has seen, which may predate us.
+            if let Some(max) = component.max().filter(|t| t.is_finite()) {
+                reading.max = Some(reading.max.map_or(max, |m| m.max(max)));
+            }
+        }
+
+        let mut readings: Vec<Reading> = self.readings.values().cloned().collect();
+        readings.sort_by(|a, b| a.label.cmp(&b.label));
+
+        SensorStats {
+            readings,
+            supplies: power_supplies(Path::new(POWER_SUPPLY_DIR)),
+        }
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    read_trimmed(path)?.parse::<f64>().ok()
+}
+
+// Values under power_supply are in micro-units (uW, uV, uA); missing files are normal.
+pub fn power_supplies(dir: &Path) -> Vec<PowerSupply> {
+    let mut supplies = Vec::new();
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return supplies;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        let power_watts = match read_number(&path.join("power_now")) {
+            Some(uw) => Some(uw / 1_000_000.0),
+            None => match (
+                read_number(&path.join("current_now")),
+                read_number(&path.join("voltage_now")),
+            ) {
+                (Some(ua), Some(uv)) => Some(ua * uv / 1_000_000_000_000.0),
+                _ => None,
+            },
+        };
+
+        supplies.push(PowerSupply {
+            name: entry.file_name().to_string_lossy().to_string(),
+            kind: read_trimmed(&path.join("type")).unwrap_or_else(|| "Unknown".to_string()),
+            status: read_trimmed(&path.join("status")),
+            capacity: read_trimmed(&path.join("capacity")).and_then(|c| c.parse::<u8>().ok()),
+            online: read_trimmed(&path.join("online")).map(|o| o == "1"),
+            power_watts,
+        });
+    }
+
+    supplies.sort_by(|a, b| a.name.cmp(&b.name));
+    supplies
+}
@@ -0,0 +1,241 @@
+use super::gatherer::ProcessInfo;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const UNIT_DIRS: [&str; 5] = [
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ServiceState {
+    Running,
+    Inactive,
+}
+
+pub struct Service {
+    pub name: String,
+    pub state: ServiceState,
+    pub enabled: bool,
+    pub main_pid: Option<u32>,
+    pub pids: Vec<u32>,
+    pub cpu: f32,
+    pub memory: u64,
+    pub unit_file: Option<PathBuf>,
+    pub description: Option<String>,
+}
+
+// Keeps unit descriptions between scans, a unit file is only read again once it changes.
+pub struct ServiceScanner {
+    descriptions: HashMap<PathBuf, (SystemTime, Option<String>)>,
+}
+
+// The innermost `*.service` component of the systemd cgroup path, on cgroup v2
+// (`0::/system.slice/nginx.service`) as well as v1 (`1:name=systemd:/...`). A process
+// in a scope nested below it, like an app launched by the user manager, has no service.
+pub fn unit_of(cgroup: &str) -> Option<String> {
+    let path = cgroup.lines().find_map(|line| {
+        let mut parts = line.splitn(3, ':');
+        let (_, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+        (controllers.is_empty() || controllers == "name=systemd").then_some(path)
+    })?;
+
+    path.split('/')
+        .rfind(|component| component.ends_with(".service") || component.ends_with(".scope"))
+        .filter(|unit| unit.ends_with(".service"))
+        .map(|unit| unit.to_string())
+}
+
+// Unit file locations in systemd's lookup order, the first one found wins.
+fn unit_files() -> HashMap<String, PathBuf> {
+    let mut files = HashMap::new();
+
+    for dir in UNIT_DIRS.iter().rev() {
+        let Ok(entries) = fs::read_dir(dir) else { continue };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Templates like getty@.service are not units by themselves.
+            if name.ends_with(".service") && !name.ends_with("@.service") {
+                files.insert(name, entry.path());
+            }
+        }
+    }
+
+    files
+}
+
+// A unit is enabled when some target's `.wants` directory links to it.
+fn enabled_units() -> HashSet<String> {
+    let mut enabled = HashSet::new();
+
+    for dir in ["/etc/systemd/system", "/run/systemd/system"] {
+        let Ok(entries) = fs::read_dir(dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_wants = path
+                .extension()
+                .is_some_and(|ext| ext == "wants" || ext == "requires");
+            if !is_wants {
+                continue;
+            }
+            let Ok(links) = fs::read_dir(&path) else { continue };
+            for link in links.flatten() {
+                enabled.insert(link.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    enabled
+}
+
+fn pids_by_unit() -> BTreeMap<String, Vec<u32>> {
+    let mut units: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+
+    // Only thread group leaders show up in /proc, so threads are not counted twice.
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return units;
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(cgroup) = fs::read_to_string(entry.path().join("cgroup")) else {
+            continue;
+        };
+        if let Some(unit) = unit_of(&cgroup) {
+            units.entry(unit).or_default().push(pid);
+        }
+    }
+
+    units
+}
+
+impl ServiceScanner {
+    pub fn new() -> Self {
+        Self {
+            descriptions: HashMap::new(),
+        }
+    }
+
+    fn describe(&mut self, path: &Path) -> Option<String> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        if let Some((at, description)) = self.descriptions.get(path)
+            && *at == modified
+        {
+            return description.clone();
+        }
+
+        let description = describe(path);
+        self.descriptions
+            .insert(path.to_path_buf(), (modified, description.clone()));
+        description
+    }
+
+    pub fn scan(&mut self, processes: &[&ProcessInfo]) -> Vec<Service> {
+        // systemd and its cgroups only exist on Linux.
+        if super::linux_only("services").is_err() {
+            return Vec::new();
+        }
+        let services = services(processes, |path| self.describe(path));
+        // Forget units whose files were removed.
+        let files: HashSet<&Path> = services.iter().filter_map(|s| s.unit_file.as_deref()).collect();
+        self.descriptions.retain(|path, _| files.contains(path.as_path()));
+        services
+    }
+}
+
+fn services(processes: &[&ProcessInfo], mut describe: impl FnMut(&Path) -> Option<String>) -> Vec<Service> {
+    let by_pid: HashMap<u32, &ProcessInfo> = processes.iter().map(|p| (p.pid, *p)).collect();
+    let mut files = unit_files();
+    let enabled = enabled_units();
+    let mut services = Vec::new();
+
+    for (name, mut pids) in pids_by_unit() {
+        pids.sort();
+        let members: HashSet<u32> = pids.iter().copied().collect();
+
+        // Without asking systemd, the main process is the lowest pid whose parent is outside the
+        // unit. That is usually the first one started, but pids wrap around so it is not guaranteed.
+        let main_pid = pids.iter().copied().find(|pid| {
+            by_pid
+                .get(pid)
+                .is_some_and(|p| !members.contains(&p.parent_pid))
+        });
+
+        let (cpu, memory) = pids
+            .iter()
+            .filter_map(|pid| by_pid.get(pid))
+            .fold((0.0, 0), |(cpu, mem), p| (cpu + p.cpu, mem + p.memory));
+
+        let unit_file = files.remove(&name);
+        services.push(Service {
+            enabled: enabled.contains(&name),
+            description: unit_file.as_deref().and_then(&mut describe),
+            unit_file,
+            name,
+            state: ServiceState::Running,
+            main_pid,
+            pids,
+            cpu,
+            memory,
+        });
+    }
+
+    for (name, path) in files {
+        services.push(Service {
+            enabled: enabled.contains(&name),
+            name,
+            state: ServiceState::Inactive,
+            main_pid: None,
+            pids: Vec::new(),
+            cpu: 0.0,
+            memory: 0,
+            description: describe(&path),
+            unit_file: Some(path),
+        });
+    }
+
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+    services
+}
+
+fn describe(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("Description="))
+        .map(|d| d.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_units() {
+        let cases = [
+            ("0::/system.slice/nginx.service", Some("nginx.service")),
+            ("1:name=systemd:/system.slice/x.service", Some("x.service")),
+            (
+                "12:cpu,cpuacct:/system.slice/other.service\n1:name=systemd:/system.slice/x.service",
+                Some("x.service"),
+            ),
+            ("0::/user.slice/user-1000.slice/user@1000.service/app.slice/foo.service", Some("foo.service")),
+            ("0::/user.slice/user-1000.slice/user@1000.service/init.scope", None),
+            ("0::/user.slice/user-1000.slice/session-2.scope", None),
+            ("0::/system.slice", None),
+            ("0::/", None),
+            ("12:cpu,cpuacct:/system.slice/x.service", None),
+            ("", None),
+        ];
+
+        for (cgroup, expected) in cases {
+            assert_eq!(unit_of(cgroup).as_deref(), expected, "{:?}", cgroup);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use super::gatherer::ProcessInfo;
+use super::procfs::{parse_stat, Stat};
+use std::fs;
+use std::io;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Signal {
+    Term,
+    Kill,
+    Hup,
+    Int,
+    Stop,
+    Cont,
+}
+
+pub const SIGNALS: [Signal; 6] = [
+    Signal::Term,
+    Signal::Kill,
+    Signal::Hup,
+    Signal::Int,
+    Signal::Stop,
+    Signal::Cont,
+];
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Order {
+    ChildrenFirst,
+    ParentFirst,
+}
+
+impl Signal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Term => "SIGTERM",
+            Signal::Kill => "SIGKILL",
+            Signal::Hup => "SIGHUP",
+            Signal::Int => "SIGINT",
+            Signal::Stop => "SIGSTOP",
+            Signal::Cont => "SIGCONT",
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn raw(self) -> libc::c_int {
+        match self {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+            Signal::Hup => libc::SIGHUP,
+            Signal::Int => libc::SIGINT,
+            Signal::Stop => libc::SIGSTOP,
+            Signal::Cont => libc::SIGCONT,
+        }
+    }
+}
+
+pub fn subtree(root: &ProcessInfo, order: Order) -> Vec<u32> {
+    fn walk(process: &ProcessInfo, order: Order, res: &mut Vec<u32>) {
+        if order == Order::ParentFirst {
+            res.push(process.pid);
+        }
+        for kid in &process.child {
+            walk(kid, order, res);
+        }
+        if order == Order::ChildrenFirst {
+            res.push(process.pid);
+        }
+    }
+
+    let mut pids = Vec::new();
+    walk(root, order, &mut pids);
+    pids
+}
+
+fn stat(pid: u32) -> Option<Stat> {
+    parse_stat(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+// A pid together with its start time names one process, a reused pid comes with a new start time.
+#[derive(PartialEq, Clone, Copy)]
+pub struct Identity {
+    pub pid: u32,
+    pub start_time: u64,
+    pub pgrp: u32,
+    pub session: u32,
+}
+
+pub fn identity(pid: u32) -> Option<Identity> {
+    let stat = stat(pid)?;
+    Some(Identity {
+        pid,
+        start_time: stat.start_time,
+        pgrp: stat.pgrp,
+        session: stat.session,
+    })
+}
+
+// Returns (process group id, session id) from /proc/<pid>/stat.
+pub fn group_and_session(pid: u32) -> Option<(u32, u32)> {
+    identity(pid).map(|id| (id.pgrp, id.session))
+}
+
+fn members(matches: impl Fn((u32, u32)) -> bool) -> io::Result<Vec<u32>> {
+    let mut pids = Vec::new();
+
+    for entry in fs::read_dir("/proc")? {
+        let Ok(entry) = entry else { continue };
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+        if group_and_session(pid).is_some_and(&matches) {
+            pids.push(pid);
+        }
+    }
+
+    pids.sort();
+    Ok(pids)
+}
+
+pub fn process_group(pgrp: u32) -> io::Result<Vec<u32>> {
+    members(|(group, _)| group == pgrp)
+}
+
+pub fn session(sid: u32) -> io::Result<Vec<u32>> {
+    members(|(_, session)| session == sid)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn send(_pid: u32, _signal: Signal) -> io::Result<()> {
+    super::linux_only("sending signals")
+}
+
+#[cfg(target_os = "linux")]
+pub fn send(pid: u32, signal: Signal) -> io::Result<()> {
+    // 0 and negative values address whole groups in kill(2), never allow them here.
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid pid")),
+    };
+
+    // SAFETY: kill only takes plain integers and reports failure through errno.
+    let res = unsafe { libc::kill(pid, signal.raw()) };
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+// Signals every pid in order and returns the ones that failed.
+pub fn send_all(pids: &[u32], signal: Signal) -> Vec<(u32, io::Error)> {
+    pids.iter()
+        .filter_map(|pid| send(*pid, signal).err().map(|e| (*pid, e)))
+        .collect()
+}
+
+// Splits targets recorded earlier into the ones that are still the same process and still
+// `belong` (e.g. to the same group), and the pids that exited or were reused meanwhile.
+pub fn still_running(targets: &[Identity], belong: impl Fn(&Identity) -> bool) -> (Vec<u32>, Vec<u32>) {
+    let mut live = Vec::new();
+    let mut gone = Vec::new();
+
+    for target in targets {
+        match identity(target.pid) {
+            Some(now) if now.start_time == target.start_time && belong(&now) => live.push(target.pid),
+            _ => gone.push(target.pid),
+        }
+    }
+
+    (live, gone)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    fn process(pid: u32, child: Vec<ProcessInfo>) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            parent_pid: 0,
+            name: String::new(),
+            cpu: 0.0,
+            memory: 0,
+            exe: String::new(),
+            user: String::new(),
+            child,
+            linux: None,
+        }
+    }
+
+    #[test]
+    fn orders_subtrees() {
+        // 1 -> (2 -> (4, 5), 3)
+        let root = process(1, vec![process(2, vec![process(4, vec![]), process(5, vec![])]), process(3, vec![])]);
+
+        assert_eq!(subtree(&root, Order::ChildrenFirst), [4, 5, 2, 3, 1]);
+        assert_eq!(subtree(&root, Order::ParentFirst), [1, 2, 4, 5, 3]);
+        assert_eq!(subtree(&process(7, vec![]), Order::ChildrenFirst), [7]);
+    }
+
+    #[test]
+    fn finds_groups_and_sessions() {
+        // A group of its own, so the group has exactly one member.
+        let mut child = Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+        let pid = child.id();
+
+        // SAFETY: getpgid and getsid only take a pid.
+        let (pgrp, sid) = unsafe { (libc::getpgid(pid as libc::pid_t), libc::getsid(pid as libc::pid_t)) };
+        let found = group_and_session(pid);
+        let members = process_group(pgrp as u32).unwrap();
+        let same_session = session(sid as u32).unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(found, Some((pgrp as u32, sid as u32)));
+        assert_eq!(pgrp as u32, pid);
+        assert_eq!(members, [pid]);
+        assert!(same_session.contains(&pid) && same_session.contains(&std::process::id()));
+    }
+
+    #[test]
+    fn notices_replaced_processes() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let id = identity(child.id()).unwrap();
+        let reused = Identity {
+            start_time: id.start_time + 1,
+            ..id
+        };
+
+        assert_eq!(still_running(&[id], |_| true), (vec![id.pid], vec![]));
+        assert_eq!(still_running(&[reused], |_| true), (vec![], vec![id.pid]));
+        assert_eq!(still_running(&[id], |now| now.pgrp != id.pgrp), (vec![], vec![id.pid]));
+
+        let _ = child.kill();
+        let _ = child.wait();
+        assert_eq!(still_running(&[id], |_| true), (vec![], vec![id.pid]));
+    }
+
+    #[test]
+    fn refuses_group_pids() {
+        for pid in [0, u32::MAX, i32::MAX as u32 + 1] {
+            assert_eq!(send(pid, Signal::Cont).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        }
+        let failed = send_all(&[0], Signal::Cont);
+        assert_eq!(failed.len(), 1);
+    }
+}
@@ -0,0 +1,409 @@
+use super::gatherer::{ProcessInfo, SysStats};
+use super::sensors::SensorStats;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const HEADER: &str = "# task_manager snapshot v1";
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum DiffSort {
+    Cpu,
+    Memory,
+}
+
+pub struct ProcessDelta {
+    pub pid: u32,
+    pub name: String,
+    pub user: String,
+    pub change: Change,
+    pub cpu_before: f32,
+    pub cpu_after: f32,
+    pub mem_before: u64,
+    pub mem_after: u64,
+}
+
+pub struct UserDelta {
+    pub user: String,
+    pub count_before: usize,
+    pub count_after: usize,
+    pub cpu_before: f32,
+    pub cpu_after: f32,
+    pub mem_before: u64,
+    pub mem_after: u64,
+}
+
+pub struct SnapshotDiff {
+    pub processes: Vec<ProcessDelta>,
+    pub users: Vec<UserDelta>,
+}
+
+impl ProcessDelta {
+    pub fn cpu_delta(&self) -> f32 {
+        self.cpu_after - self.cpu_before
+    }
+
+    pub fn mem_delta(&self) -> i64 {
+        self.mem_after as i64 - self.mem_before as i64
+    }
+}
+
+impl UserDelta {
+    pub fn cpu_delta(&self) -> f32 {
+        self.cpu_after - self.cpu_before
+    }
+
+    pub fn mem_delta(&self) -> i64 {
+        self.mem_after as i64 - self.mem_before as i64
+    }
+}
+
+pub fn flatten(processes: &[ProcessInfo]) -> Vec<&ProcessInfo> {
+    fn dfs<'a>(process: &'a [ProcessInfo], res: &mut Vec<&'a ProcessInfo>) {
+        for proc in process.iter() {
+            res.push(proc);
+            dfs(&proc.child, res);
+        }
+    }
+
+    let mut list = Vec::new();
+    dfs(processes, &mut list);
+    list
+}
+
+fn clean(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+pub fn save(stats: &SysStats, path: &Path) -> io::Result<()> {
+    let mut out = String::new();
+    out.push_str(HEADER);
+    out.push('\n');
+    out.push_str(&format!(
+        "{}\t{}\t{}\t{}\n",
+        stats.cpu, stats.used_mem, stats.total_mem, stats.cores
+    ));
+
+    for process in flatten(&stats.processes) {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            process.pid,
+            process.parent_pid,
+            process.cpu,
+            process.memory,
+            clean(&process.user),
+            clean(&process.name),
+            clean(&process.exe)
+        ));
+    }
+
+    fs::write(path, out)
+}
+
+fn invalid(line: usize, what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, what),
+    )
+}
+
+// Processes whose parent is missing from the file become roots.
+fn rebuild(flat: Vec<ProcessInfo>) -> Vec<ProcessInfo> {
+    let pids: HashSet<u32> = flat.iter().map(|p| p.pid).collect();
+    let mut families: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
+    let mut roots = Vec::new();
+
+    for process in flat {
+        if process.parent_pid != process.pid && pids.contains(&process.parent_pid) {
+            families.entry(process.parent_pid).or_default().push(process);
+        } else {
+            roots.push(process);
+        }
+    }
+
+    fn attach(process: &mut ProcessInfo, families: &mut HashMap<u32, Vec<ProcessInfo>>) {
+        process.child = families.remove(&process.pid).unwrap_or_default();
+        for kid in &mut process.child {
+            attach(kid, families);
+        }
+    }
+
+    for root in &mut roots {
+        attach(root, &mut families);
+    }
+
+    roots
+}
+
+pub fn load(path: &Path) -> io::Result<SysStats> {
+    let contents = fs::read_to_string(path)?;
+    let mut lines = contents.lines().enumerate();
+
+    match lines.next() {
+        Some((_, HEADER)) => (),
+        _ => return Err(invalid(1, "not a task_manager snapshot")),
+    }
+
+    let (_, totals) = lines.next().ok_or_else(|| invalid(2, "missing totals"))?;
+    let totals: Vec<&str> = totals.split('\t').collect();
+    if totals.len() != 4 {
+        return Err(invalid(2, "expected cpu, used memory, total memory and cores"));
+    }
+    let number = |field: &str| field.parse::<f64>().map_err(|_| invalid(2, "bad number"));
+
+    let mut flat = Vec::new();
+    for (index, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.splitn(7, '\t').collect();
+        if fields.len() != 7 {
+            return Err(invalid(index + 1, "expected 7 fields"));
+        }
+        let bad = || invalid(index + 1, "bad number");
+
+        flat.push(ProcessInfo {
+            pid: fields[0].parse().map_err(|_| bad())?,
+            parent_pid: fields[1].parse().map_err(|_| bad())?,
+            cpu: fields[2].parse().map_err(|_| bad())?,
+            memory: fields[3].parse().map_err(|_| bad())?,
+            user: fields[4].to_string(),
+            name: fields[5].to_string(),
+            exe: fields[6].to_string(),
+            child: Vec::new(),
+            linux: None,
+        });
+    }
+
+    Ok(SysStats {
+        processes: rebuild(flat),
+        cpu: number(totals[0])? as f32,
+        used_mem: number(totals[1])? as u64,
+        total_mem: number(totals[2])? as u64,
+        cores: number(totals[3])? as usize,
+        sensors: SensorStats::default(),
+    })
+}
+
+// A pid that now belongs to a different program counts as removed plus added.
+pub fn diff(before: &SysStats, after: &SysStats, sort: DiffSort) -> SnapshotDiff {
+    let old: HashMap<u32, &ProcessInfo> =
+        flatten(&before.processes).into_iter().map(|p| (p.pid, p)).collect();
+    let new: HashMap<u32, &ProcessInfo> =
+        flatten(&after.processes).into_iter().map(|p| (p.pid, p)).collect();
+
+    let mut processes = Vec::new();
+    let delta = |p: &ProcessInfo, change: Change, before: Option<&ProcessInfo>| ProcessDelta {
+        pid: p.pid,
+        name: p.name.clone(),
+        user: p.user.clone(),
+        change,
+        cpu_before: before.map_or(0.0, |b| b.cpu),
+        cpu_after: if change == Change::Removed { 0.0 } else { p.cpu },
+        mem_before: before.map_or(0, |b| b.memory),
+        mem_after: if change == Change::Removed { 0 } else { p.memory },
+    };
+
+    for (pid, process) in &new {
+        match old.get(pid) {
+            Some(previous) if previous.name == process.name => {
+                processes.push(delta(process, Change::Changed, Some(previous)));
+            }
+            Some(previous) => {
+                processes.push(delta(previous, Change::Removed, Some(previous)));
+                processes.push(delta(process, Change::Added, None));
+            }
+            None => processes.push(delta(process, Change::Added, None)),
+        }
+    }
+    for (pid, process) in &old {
+        if !new.contains_key(pid) {
+            processes.push(delta(process, Change::Removed, Some(process)));
+        }
+    }
+
+    let mut users: BTreeMap<String, UserDelta> = BTreeMap::new();
+    let blank = |user: &str| UserDelta {
+        user: user.to_string(),
+        count_before: 0,
+        count_after: 0,
+        cpu_before: 0.0,
+        cpu_after: 0.0,
+        mem_before: 0,
+        mem_after: 0,
+    };
+    for process in old.values() {
+        let entry = users
+            .entry(process.user.clone())
+            .or_insert_with(|| blank(&process.user));
+        entry.count_before += 1;
+        entry.cpu_before += process.cpu;
+        entry.mem_before += process.memory;
+    }
+    for process in new.values() {
+        let entry = users
+            .entry(process.user.clone())
+            .or_insert_with(|| blank(&process.user));
+        entry.count_after += 1;
+        entry.cpu_after += process.cpu;
+        entry.mem_after += process.memory;
+    }
+    let mut users: Vec<UserDelta> = users.into_values().collect();
+
+    match sort {
+        DiffSort::Cpu => {
+            processes.sort_by(|a, b| b.cpu_delta().abs().total_cmp(&a.cpu_delta().abs()));
+            users.sort_by(|a, b| b.cpu_delta().abs().total_cmp(&a.cpu_delta().abs()));
+        }
+        DiffSort::Memory => {
+            processes.sort_by_key(|p| std::cmp::Reverse(p.mem_delta().unsigned_abs()));
+            users.sort_by_key(|u| std::cmp::Reverse(u.mem_delta().unsigned_abs()));
+        }
+    }
+
+    SnapshotDiff { processes, users }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn process(pid: u32, parent_pid: u32, name: &str, cpu: f32, memory: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            parent_pid,
+            name: name.to_string(),
+            cpu,
+            memory,
+            exe: format!("/usr/bin/{}", name),
+            user: "alice".to_string(),
+            child: Vec::new(),
+            linux: None,
+        }
+    }
+
+    fn stats(processes: Vec<ProcessInfo>) -> SysStats {
+        SysStats {
+            processes,
+            cpu: 12.5,
+            used_mem: 1024,
+            total_mem: 4096,
+            cores: 4,
+            sensors: SensorStats::default(),
+        }
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("task_manager_snapshot_{}_{}", name, std::process::id()))
+    }
+
+    fn load_text(name: &str, contents: &str) -> io::Result<SysStats> {
+        let path = temp_file(name);
+        fs::write(&path, contents).unwrap();
+        let loaded = load(&path);
+        let _ = fs::remove_file(&path);
+        loaded
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut init = process(1, 0, "init", 0.5, 100);
+        let mut shell = process(2, 1, "my\tshell", 1.5, 200);
+        shell.exe = "/opt/odd\nname\r".to_string();
+        shell.child = vec![process(3, 2, "vim", 3.0, 300)];
+        init.child = vec![shell];
+        // Its parent is not in the snapshot, so it comes back as a root.
+        let orphan = process(10, 99, "orphan", 0.0, 400);
+
+        let path = temp_file("round_trip");
+        save(&stats(vec![init, orphan]), &path).unwrap();
+        let loaded = load(&path);
+        let _ = fs::remove_file(&path);
+        let loaded = loaded.unwrap();
+
+        assert_eq!((loaded.cpu, loaded.used_mem, loaded.total_mem, loaded.cores), (12.5, 1024, 4096, 4));
+        let roots: Vec<u32> = loaded.processes.iter().map(|p| p.pid).collect();
+        assert_eq!(roots, [1, 10]);
+        assert_eq!(loaded.processes[1].parent_pid, 99);
+
+        let shell = &loaded.processes[0].child[0];
+        assert_eq!(shell.name, "my shell");
+        assert_eq!(shell.exe, "/opt/odd name ");
+        assert_eq!((shell.cpu, shell.memory, shell.user.as_str()), (1.5, 200, "alice"));
+        assert_eq!(shell.child[0].pid, 3);
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let totals = "12.5\t1024\t4096\t4\n";
+        let good = "1\t0\t0.5\t100\talice\tinit\t/sbin/init\n";
+        let cases = [
+            (String::new(), "line 1: not a task_manager snapshot"),
+            ("# something else\n".to_string(), "line 1: not a task_manager snapshot"),
+            (format!("{HEADER}\n"), "line 2: missing totals"),
+            (format!("{HEADER}\n12.5\t1024\n"), "line 2: expected cpu, used memory, total memory and cores"),
+            (format!("{HEADER}\n12.5\tlots\t4096\t4\n"), "line 2: bad number"),
+            (format!("{HEADER}\n{totals}{good}2\t1\t0.5\n"), "line 4: expected 7 fields"),
+            (format!("{HEADER}\n{totals}{good}\n2\tx\t0.5\t100\talice\tsh\t/bin/sh\n"), "line 5: bad number"),
+            (format!("{HEADER}\n{totals}2\t1\t0.5\t-100\talice\tsh\t/bin/sh\n"), "line 3: bad number"),
+        ];
+
+        for (index, (contents, error)) in cases.iter().enumerate() {
+            let err = load_text(&format!("broken_{}", index), contents).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", contents);
+            assert_eq!(err.to_string(), *error, "{:?}", contents);
+        }
+    }
+
+    #[test]
+    fn diffs_snapshots() {
+        let before = stats(vec![
+            process(1, 0, "init", 1.0, 1000),
+            process(2, 0, "old", 4.0, 5000),
+            process(3, 0, "reused", 2.0, 2000),
+        ]);
+        let after = stats(vec![
+            process(1, 0, "init", 1.5, 2500),
+            process(3, 0, "other", 10.0, 200),
+            process(4, 0, "new", 8.0, 8000),
+        ]);
+
+        let changes = |diff: &SnapshotDiff| -> Vec<(u32, &str)> {
+            diff.processes
+                .iter()
+                .map(|p| {
+                    let change = match p.change {
+                        Change::Added => "added",
+                        Change::Removed => "removed",
+                        Change::Changed => "changed",
+                    };
+                    (p.pid, change)
+                })
+                .collect()
+        };
+
+        let by_cpu = diff(&before, &after, DiffSort::Cpu);
+        // The reused pid shows up twice, as the program that left and the one that replaced it.
+        assert_eq!(changes(&by_cpu), [(3, "added"), (4, "added"), (2, "removed"), (3, "removed"), (1, "changed")]);
+        assert_eq!(by_cpu.processes[0].name, "other");
+        assert_eq!((by_cpu.processes[3].cpu_after, by_cpu.processes[3].mem_after), (0.0, 0));
+        assert_eq!(by_cpu.processes[4].mem_delta(), 1500);
+
+        let user = &by_cpu.users[0];
+        assert_eq!((user.count_before, user.count_after), (3, 3));
+        assert_eq!(user.mem_delta(), 10700 - 8000);
+
+        let by_memory = diff(&before, &after, DiffSort::Memory);
+        assert_eq!(changes(&by_memory), [(4, "added"), (2, "removed"), (3, "removed"), (1, "changed"), (3, "added")]);
+    }
+}
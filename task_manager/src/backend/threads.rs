@@ -0,0 +1,106 @@
+use super::procfs::{parse_stat, ticks_per_second};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::time::Instant;
+
+pub struct ThreadInfo {
+    pub tid: u32,
+    pub name: String,
+    pub state: char,
+    pub cpu: f32,
+    pub processor: Option<u32>,
+    pub affinity: String,
+}
+
+fn affinity(pid: u32, tid: u32) -> String {
+    let status = fs::read_to_string(format!("/proc/{}/task/{}/status", pid, tid)).unwrap_or_default();
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .map(|list| list.trim().to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+pub fn state_name(state: char) -> &'static str {
+    match state {
+        'R' => "Running",
+        'S' => "Sleeping",
+        'D' => "Disk sleep",
+        'T' => "Stopped",
+        't' => "Tracing stop",
+        'Z' => "Zombie",
+        'X' => "Dead",
+        'I' => "Idle",
+        _ => "Unknown",
+    }
+}
+
+// CPU usage needs two samples, so the sampler remembers the previous tick counts.
+pub struct ThreadSampler {
+    pid: u32,
+    last: HashMap<u32, u64>,
+    at: Instant,
+}
+
+impl ThreadSampler {
+    pub fn new() -> Self {
+        Self {
+            pid: 0,
+            last: HashMap::new(),
+            at: Instant::now(),
+        }
+    }
+
+    pub fn sample(&mut self, pid: u32) -> io::Result<Vec<ThreadInfo>> {
+        super::linux_only("listing threads")?;
+        if pid != self.pid {
+            self.pid = pid;
+            self.last.clear();
+        }
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.at).as_secs_f64();
+        self.at = now;
+
+        let mut threads = Vec::new();
+        let mut ticks = HashMap::new();
+
+        for entry in fs::read_dir(format!("/proc/{}/task", pid))? {
+            let Ok(entry) = entry else { continue };
+            let Some(tid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+                continue;
+            };
+            let Some(stat) = fs::read_to_string(entry.path().join("stat"))
+                .ok()
+                .and_then(|c| parse_stat(&c))
+            else {
+                continue;
+            };
+
+            let cpu = match self.last.get(&tid) {
+                Some(prev) if elapsed > 0.0 => {
+                    let delta = stat.ticks.saturating_sub(*prev) as f64;
+                    (delta / ticks_per_second() / elapsed * 100.0) as f32
+                }
+                _ => 0.0,
+            };
+            ticks.insert(tid, stat.ticks);
+
+            threads.push(ThreadInfo {
+                tid,
+                name: stat.name,
+                state: stat.state,
+                cpu,
+                processor: stat.processor,
+                affinity: affinity(pid, tid),
+            });
+        }
+
+        self.last = ticks;
+        threads.sort_by(|a, b| b.cpu.total_cmp(&a.cpu).then(a.tid.cmp(&b.tid)));
+
+        Ok(threads)
+    }
+}
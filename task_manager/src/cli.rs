@@ -0,0 +1,590 @@
+use crate::backend::gatherer::{Backend, InfoGetter, Monitor, ProcessInfo, SysStats};
+use crate::backend::lookup::{self, Holder};
+use crate::backend::procfs::ProcMonitor;
+use crate::backend::snapshot::{self, Change, DiffSort, SnapshotDiff};
+use crate::format::FormatOptions;
+use crate::query::{Expr, Field};
+use crate::{FilterType, SortCriteria, SortType, TaskManager};
+use std::collections::HashSet;
+use std::env::var;
+use std::io::{self, Write};
+use std::path::Path;
+use std::{thread, time};
+
+const USAGE: &str = "usage:
+    task_manager [--backend sysinfo|proc] [command]
+                                     --backend picks how processes are read (default sysinfo)
+    task_manager                     start the graphical interface
+    task_manager who-file <path>     list processes that have <path> open
+    task_manager who-port <port>     list processes with a socket bound to <port>
+    task_manager snapshot <file>     save the current processes to <file>
+    task_manager diff <before> <after> [--sort cpu|memory] [--limit N]
+                                     compare two saved snapshots
+    task_manager ps [options]        print one snapshot of the process list
+        --sort cpu|memory|name       sort key (default cpu)
+        --order asc|desc             sort order (default desc)
+        --filter all|user|system|<expr>
+                                     which processes to show (default all), an expression
+                                     looks like 'user=root && cpu>5 && memory>=100M'
+        --columns pid,ppid,name,cpu,memory,exe,user
+        --tree                       print the process tree instead of a flat list
+        --format table|json|csv      output format (default table)";
+
+// Returns None when no subcommand was given, so main can start the GUI instead.
+pub fn run(args: &[String], backend: Backend) -> Option<i32> {
+    let command = args.first()?;
+
+    let code = match (command.as_str(), args.get(1)) {
+        ("who-file", Some(path)) => print_holders(lookup::who_has_file(Path::new(path))),
+        ("who-port", Some(port)) => match port.parse::<u16>() {
+            Ok(port) => print_holders(lookup::who_uses_port(port)),
+            Err(e) => {
+                eprintln!("Invalid port '{port}': {e}");
+                2
+            }
+        },
+        ("snapshot", Some(path)) => save_snapshot(Path::new(path), backend),
+        ("diff", Some(_)) => diff_snapshots(&args[1..]),
+        ("ps", _) => ps(&args[1..], backend),
+        ("help" | "--help" | "-h", _) => {
+            println!("{USAGE}");
+            0
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            2
+        }
+    };
+
+    Some(code)
+}
+
+fn print_holders(result: std::io::Result<Vec<Holder>>) -> i32 {
+    let holders = match result {
+        Ok(h) => h,
+        Err(e) => {
+            eprintln!("Lookup failed: {e}");
+            return 1;
+        }
+    };
+
+    if holders.is_empty() {
+        println!("No process found (processes of other users are only visible as root)");
+        return 1;
+    }
+
+    println!("{:>8}  {:<20}  {:>5}  WHAT", "PID", "NAME", "FD");
+    for holder in holders {
+        println!(
+            "{:>8}  {:<20}  {:>5}  {}",
+            holder.pid, holder.name, holder.fd, holder.what
+        );
+    }
+
+    0
+}
+
+// CPU usage is measured between two refreshes, so give the first one time to settle.
+fn sample(backend: Backend) -> SysStats {
+    match backend {
+        Backend::Sysinfo => sample_with::<Monitor>(),
+        Backend::Proc => sample_with::<ProcMonitor>(),
+    }
+}
+
+fn sample_with<G: InfoGetter>() -> SysStats {
+    let mut monitor = G::new();
+    thread::sleep(time::Duration::from_millis(1000));
+    monitor.system_info_update()
+}
+
+fn save_snapshot(path: &Path, backend: Backend) -> i32 {
+    let stats = sample(backend);
+
+    match snapshot::save(&stats, path) {
+        Ok(()) => {
+            println!("Snapshot saved to {}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Couldn't save snapshot: {e}");
+            1
+        }
+    }
+}
+
+fn diff_snapshots(args: &[String]) -> i32 {
+    let mut paths = Vec::new();
+    let mut sort = DiffSort::Cpu;
+    let mut limit = 20usize;
+    let mut i = 0;
+
+    while i < args.len() {
+        match args[i].as_str() {
+            "--sort" => {
+                sort = match args.get(i + 1).map(|s| s.as_str()) {
+                    Some("cpu") => DiffSort::Cpu,
+                    Some("memory") => DiffSort::Memory,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return 2;
+                    }
+                };
+                i += 2;
+            }
+            "--limit" => {
+                limit = match args.get(i + 1).and_then(|n| n.parse::<usize>().ok()) {
+                    Some(n) => n,
+                    None => {
+                        eprintln!("{USAGE}");
+                        return 2;
+                    }
+                };
+                i += 2;
+            }
+            path => {
+                paths.push(path);
+                i += 1;
+            }
+        }
+    }
+
+    let [before, after] = paths[..] else {
+        eprintln!("{USAGE}");
+        return 2;
+    };
+
+    let load = |path: &str| {
+        snapshot::load(Path::new(path)).map_err(|e| eprintln!("Couldn't load {path}: {e}"))
+    };
+    let (Ok(before), Ok(after)) = (load(before), load(after)) else {
+        return 1;
+    };
+
+    print_diff(&snapshot::diff(&before, &after, sort), after.cores, limit);
+    0
+}
+
+fn print_diff(diff: &SnapshotDiff, cores: usize, limit: usize) {
+    let fmt = FormatOptions::default();
+    let count = |change| diff.processes.iter().filter(|p| p.change == change).count();
+
+    println!(
+        "{} added, {} removed, {} still running",
+        count(Change::Added),
+        count(Change::Removed),
+        count(Change::Changed)
+    );
+    println!();
+    println!(
+        "{:<8}  {:>8}  {:<20}  {:<12}  {:>10}  {:>12}",
+        "CHANGE", "PID", "NAME", "USER", "CPU", "MEMORY"
+    );
+    for process in diff.processes.iter().take(limit) {
+        let change = match process.change {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Changed => "changed",
+        };
+        println!(
+            "{:<8}  {:>8}  {:<20}  {:<12}  {:>10}  {:>12}",
+            change,
+            process.pid,
+            process.name,
+            process.user,
+            fmt.cpu_delta(process.cpu_delta(), cores),
+            fmt.memory_delta(process.mem_delta())
+        );
+    }
+
+    println!();
+    println!(
+        "{:<12}  {:>10}  {:>10}  {:>12}",
+        "USER", "PROCESSES", "CPU", "MEMORY"
+    );
+    for user in diff.users.iter().take(limit) {
+        println!(
+            "{:<12}  {:>10}  {:>10}  {:>12}",
+            user.user,
+            format!("{} -> {}", user.count_before, user.count_after),
+            fmt.cpu_delta(user.cpu_delta(), cores),
+            fmt.memory_delta(user.mem_delta())
+        );
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+fn usage_error(message: &str) -> i32 {
+    eprintln!("{message}");
+    eprintln!("{USAGE}");
+    2
+}
+
+// What `ps` lists and in which order, apart from sampling and printing.
+struct Listing {
+    criteria: SortCriteria,
+    sort_type: SortType,
+    filter: FilterType,
+    expr: Option<Expr>,
+    tree: bool,
+}
+
+impl Listing {
+    // In a tree the expression has to be applied before the depths are worked out.
+    fn prepare(&self, processes: Vec<ProcessInfo>, cores: usize) -> Vec<ProcessInfo> {
+        match (self.tree, &self.expr) {
+            (true, Some(expr)) => prune(processes, expr, cores),
+            _ => processes,
+        }
+    }
+
+    // The same view functions the GUI draws from, so both list processes identically.
+    fn rows<'a>(&self, processes: &'a [ProcessInfo], cores: usize, user: &String) -> Vec<(&'a ProcessInfo, u8)> {
+        if self.tree {
+            let open: HashSet<u32> = snapshot::flatten(processes).iter().map(|p| p.pid).collect();
+            TaskManager::data_tree_view(processes, self.criteria, self.sort_type, self.filter, user, &open)
+        } else {
+            TaskManager::data_table_view(processes, self.criteria, self.sort_type, self.filter, user)
+                .into_iter()
+                .filter(|p| self.expr.as_ref().is_none_or(|expr| expr.matches(p, cores)))
+                .map(|p| (p, 0))
+                .collect()
+        }
+    }
+}
+
+fn ps(args: &[String], backend: Backend) -> i32 {
+    let mut criteria = SortCriteria::Cpu;
+    let mut sort_type = SortType::Descending;
+    let mut filter = FilterType::All;
+    let mut expr: Option<Expr> = None;
+    let mut columns = vec![Field::Pid, Field::Name, Field::Cpu, Field::Memory, Field::User];
+    let mut tree = false;
+    let mut format = OutputFormat::Table;
+    let mut i = 0;
+
+    while i < args.len() {
+        let value = args.get(i + 1).map(|s| s.as_str());
+
+        match (args[i].as_str(), value) {
+            ("--tree", _) => {
+                tree = true;
+                i += 1;
+                continue;
+            }
+            ("--sort", Some("cpu")) => criteria = SortCriteria::Cpu,
+            ("--sort", Some("memory")) => criteria = SortCriteria::Memory,
+            ("--sort", Some("name")) => criteria = SortCriteria::Name,
+            ("--order", Some("asc")) => sort_type = SortType::Ascending,
+            ("--order", Some("desc")) => sort_type = SortType::Descending,
+            ("--filter", Some("all")) => filter = FilterType::All,
+            ("--filter", Some("user")) => filter = FilterType::User,
+            ("--filter", Some("system")) => filter = FilterType::System,
+            ("--filter", Some(text)) => match Expr::parse(text) {
+                Ok(parsed) => expr = Some(parsed),
+                Err(e) => return usage_error(&format!("Invalid filter: {e}")),
+            },
+            ("--columns", Some(list)) => {
+                let parsed: Option<Vec<Field>> = list.split(',').map(Field::parse).collect();
+                match parsed {
+                    Some(parsed) if !parsed.is_empty() => columns = parsed,
+                    _ => return usage_error(&format!("Invalid column list '{list}'")),
+                }
+            }
+            ("--format", Some("table")) => format = OutputFormat::Table,
+            ("--format", Some("json")) => format = OutputFormat::Json,
+            ("--format", Some("csv")) => format = OutputFormat::Csv,
+            (option, _) => return usage_error(&format!("Unexpected argument '{option}'")),
+        }
+        i += 2;
+    }
+
+    let listing = Listing {
+        criteria,
+        sort_type,
+        filter,
+        expr,
+        tree,
+    };
+    let mut stats = sample(backend);
+    let user = var("USER").unwrap_or_else(|_| "unknown".to_string());
+
+    stats.processes = listing.prepare(std::mem::take(&mut stats.processes), stats.cores);
+    let rows = listing.rows(&stats.processes, stats.cores, &user);
+
+    let mut out = io::stdout().lock();
+    let res = match format {
+        OutputFormat::Table => print_table(&mut out, &rows, &columns, &stats),
+        OutputFormat::Json => print_json(&mut out, &rows, &columns, &stats, tree),
+        OutputFormat::Csv => print_csv(&mut out, &rows, &columns, &stats, tree),
+    };
+
+    // A reader such as `head` closing the pipe early is not an error.
+    match res.and_then(|()| out.flush()) {
+        Ok(()) => 0,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(e) => {
+            eprintln!("Couldn't write the process list: {e}");
+            1
+        }
+    }
+}
+
+// Drops the processes that don't match, moving their children up to the nearest kept ancestor.
+fn prune(processes: Vec<ProcessInfo>, expr: &Expr, cores: usize) -> Vec<ProcessInfo> {
+    let mut kept = Vec::new();
+
+    for mut process in processes {
+        let children = prune(std::mem::take(&mut process.child), expr, cores);
+        if expr.matches(&process, cores) {
+            process.child = children;
+            kept.push(process);
+        } else {
+            kept.extend(children);
+        }
+    }
+
+    kept
+}
+
+fn raw_value(process: &ProcessInfo, field: Field, cores: usize) -> String {
+    match field {
+        Field::Pid => process.pid.to_string(),
+        Field::ParentPid => process.parent_pid.to_string(),
+        Field::Name => process.name.clone(),
+        Field::Cpu => format!("{:.2}", process.cpu / cores.max(1) as f32),
+        Field::Memory => process.memory.to_string(),
+        Field::Exe => process.exe.clone(),
+        Field::User => process.user.clone(),
+    }
+}
+
+fn print_table(
+    out: &mut impl Write,
+    rows: &[(&ProcessInfo, u8)],
+    columns: &[Field],
+    stats: &SysStats,
+) -> io::Result<()> {
+    let fmt = FormatOptions::default();
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|(process, depth)| {
+            columns
+                .iter()
+                .map(|field| match field {
+                    Field::Name => format!("{}{}", "  ".repeat(*depth as usize), process.name),
+                    Field::Cpu => fmt.cpu(process.cpu, stats.cores),
+                    Field::Memory => fmt.memory(process.memory, stats.total_mem),
+                    _ => raw_value(process, *field, stats.cores),
+                })
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([field.key().len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let header: Vec<String> = columns
+        .iter()
+        .zip(&widths)
+        .map(|(field, width)| format!("{:<width$}", field.key().to_uppercase()))
+        .collect();
+    writeln!(out, "{}", header.join("  ").trim_end())?;
+
+    for row in cells {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell))
+            .collect();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn print_json(
+    out: &mut impl Write,
+    rows: &[(&ProcessInfo, u8)],
+    columns: &[Field],
+    stats: &SysStats,
+    tree: bool,
+) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (index, (process, depth)) in rows.iter().enumerate() {
+        let mut fields: Vec<String> = columns
+            .iter()
+            .map(|field| {
+                let value = raw_value(process, *field, stats.cores);
+                match field {
+                    Field::Name | Field::Exe | Field::User => {
+                        format!("{}: {}", json_string(field.key()), json_string(&value))
+                    }
+                    _ => format!("{}: {}", json_string(field.key()), value),
+                }
+            })
+            .collect();
+        if tree {
+            fields.push(format!("\"depth\": {}", depth));
+        }
+
+        let comma = if index + 1 < rows.len() { "," } else { "" };
+        writeln!(out, "  {{{}}}{}", fields.join(", "), comma)?;
+    }
+    writeln!(out, "]")
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn print_csv(
+    out: &mut impl Write,
+    rows: &[(&ProcessInfo, u8)],
+    columns: &[Field],
+    stats: &SysStats,
+    tree: bool,
+) -> io::Result<()> {
+    let mut header: Vec<&str> = columns.iter().map(|f| f.key()).collect();
+    if tree {
+        header.push("depth");
+    }
+    writeln!(out, "{}", header.join(","))?;
+
+    for (process, depth) in rows {
+        let mut line: Vec<String> = columns
+            .iter()
+            .map(|field| csv_field(&raw_value(process, *field, stats.cores)))
+            .collect();
+        if tree {
+            line.push(depth.to_string());
+        }
+        writeln!(out, "{}", line.join(","))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, user: &str, cpu: f32, child: Vec<ProcessInfo>) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            parent_pid: 0,
+            name: name.to_string(),
+            cpu,
+            memory: pid as u64 * 1024,
+            exe: String::new(),
+            user: user.to_string(),
+            child,
+            linux: None,
+        }
+    }
+
+    fn fixture() -> Vec<ProcessInfo> {
+        vec![
+            process(
+                1,
+                "systemd",
+                "root",
+                1.0,
+                vec![
+                    process(
+                        2,
+                        "sshd",
+                        "root",
+                        5.0,
+                        vec![process(3, "bash", "alice", 30.0, vec![process(4, "vim", "alice", 50.0, vec![])])],
+                    ),
+                    process(5, "cron", "root", 2.0, vec![]),
+                ],
+            ),
+            process(6, "tmux", "alice", 10.0, vec![]),
+        ]
+    }
+
+    fn listing(criteria: SortCriteria, filter: FilterType, expr: Option<&str>, tree: bool) -> Listing {
+        Listing {
+            criteria,
+            sort_type: SortType::Descending,
+            filter,
+            expr: expr.map(|text| Expr::parse(text).unwrap()),
+            tree,
+        }
+    }
+
+    fn pids(rows: &[(&ProcessInfo, u8)]) -> Vec<(u32, u8)> {
+        rows.iter().map(|(p, depth)| (p.pid, *depth)).collect()
+    }
+
+    #[test]
+    fn prunes_the_tree_before_nesting() {
+        let listing = listing(SortCriteria::Cpu, FilterType::All, Some("user=alice"), true);
+        let processes = listing.prepare(fixture(), 1);
+        let rows = listing.rows(&processes, 1, &"alice".to_string());
+
+        // bash moves up to the top level in place of the root-owned ancestors it lost.
+        assert_eq!(pids(&rows), [(3, 0), (4, 1), (6, 0)]);
+    }
+
+    #[test]
+    fn lists_like_the_gui() {
+        let user = "alice".to_string();
+        let processes = fixture();
+        let open: HashSet<u32> = (1..=6).collect();
+
+        for criteria in [SortCriteria::Cpu, SortCriteria::Memory, SortCriteria::Name] {
+            for filter in [FilterType::All, FilterType::User, FilterType::System] {
+                let table = TaskManager::data_table_view(&processes, criteria, SortType::Descending, filter, &user);
+                let table: Vec<(u32, u8)> = table.iter().map(|p| (p.pid, 0)).collect();
+                let rows = listing(criteria, filter, None, false).rows(&processes, 1, &user);
+                assert_eq!(pids(&rows), table);
+
+                let tree = TaskManager::data_tree_view(&processes, criteria, SortType::Descending, filter, &user, &open);
+                let rows = listing(criteria, filter, None, true).rows(&processes, 1, &user);
+                assert_eq!(pids(&rows), pids(&tree));
+            }
+        }
+
+        let rows = listing(SortCriteria::Cpu, FilterType::All, Some("cpu>=10"), false).rows(&processes, 1, &user);
+        assert_eq!(pids(&rows), [(4, 0), (3, 0), (6, 0)]);
+    }
+}
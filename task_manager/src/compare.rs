@@ -0,0 +1,150 @@
+use crate::TaskManager;
+use crate::backend::snapshot::{self, Change, DiffSort, SnapshotDiff};
+use crate::i18n::{tr, trf};
+use eframe::egui;
+use std::path::Path;
+
+const SHOWN_ROWS: usize = 200;
+
+pub struct CompareDialog {
+    pub open: bool,
+    save_path: String,
+    before: String,
+    after: String,
+    sort: DiffSort,
+    result: Option<(SnapshotDiff, usize)>,
+    message: Option<String>,
+}
+
+impl Default for CompareDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            save_path: "snapshot.tsv".to_string(),
+            before: String::new(),
+            after: String::new(),
+            sort: DiffSort::Cpu,
+            result: None,
+            message: None,
+        }
+    }
+}
+
+impl TaskManager {
+    pub fn compare_window(&mut self, ctx: &egui::Context) {
+        let stats = &self.stats;
+        let fmt = &self.format;
+        let dialog = &mut self.compare;
+        let mut open = dialog.open;
+
+        egui::Window::new(tr("Snapshots"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(700.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Save current to"));
+                    ui.text_edit_singleline(&mut dialog.save_path);
+                    if ui.button(tr("Save")).clicked() {
+                        dialog.message = Some(match snapshot::save(stats, Path::new(&dialog.save_path)) {
+                            Ok(()) => trf("Saved {}", &[&dialog.save_path]),
+                            Err(e) => trf("Couldn't save: {}", &[&e]),
+                        });
+                    }
+                });
+                ui.separator();
+
+                egui::Grid::new("compare_paths").num_columns(2).show(ui, |ui| {
+                    ui.label(tr("Before"));
+                    ui.text_edit_singleline(&mut dialog.before);
+                    ui.end_row();
+                    ui.label(tr("After"));
+                    ui.text_edit_singleline(&mut dialog.after);
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label(tr("Largest change in"));
+                    ui.radio_value(&mut dialog.sort, DiffSort::Cpu, tr("CPU"));
+                    ui.radio_value(&mut dialog.sort, DiffSort::Memory, tr("Memory"));
+
+                    if ui.button(tr("Compare")).clicked() {
+                        let before = snapshot::load(Path::new(&dialog.before));
+                        let after = snapshot::load(Path::new(&dialog.after));
+
+                        match (before, after) {
+                            (Ok(before), Ok(after)) => {
+                                let diff = snapshot::diff(&before, &after, dialog.sort);
+                                dialog.result = Some((diff, after.cores));
+                                dialog.message = None;
+                            }
+                            (Err(e), _) => dialog.message = Some(trf("Before: {}", &[&e])),
+                            (_, Err(e)) => dialog.message = Some(trf("After: {}", &[&e])),
+                        }
+                    }
+                });
+
+                if let Some(message) = &dialog.message {
+                    ui.label(message);
+                }
+
+                let Some((diff, cores)) = &dialog.result else {
+                    return;
+                };
+
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.heading(tr("Processes"));
+                    egui::Grid::new("compare_processes")
+                        .num_columns(6)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong(tr("Change"));
+                            ui.strong(tr("PID"));
+                            ui.strong(tr("Name"));
+                            ui.strong(tr("User"));
+                            ui.strong(tr("CPU"));
+                            ui.strong(tr("Memory"));
+                            ui.end_row();
+
+                            for process in diff.processes.iter().take(SHOWN_ROWS) {
+                                match process.change {
+                                    Change::Added => ui.colored_label(egui::Color32::GREEN, tr("added")),
+                                    Change::Removed => ui.colored_label(egui::Color32::RED, tr("removed")),
+                                    Change::Changed => ui.label(tr("changed")),
+                                };
+                                ui.label(process.pid.to_string());
+                                ui.label(&process.name);
+                                ui.label(&process.user);
+                                ui.label(fmt.cpu_delta(process.cpu_delta(), *cores));
+                                ui.label(fmt.memory_delta(process.mem_delta()));
+                                ui.end_row();
+                            }
+                        });
+
+                    ui.separator();
+                    ui.heading(tr("Users"));
+                    egui::Grid::new("compare_users")
+                        .num_columns(4)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong(tr("User"));
+                            ui.strong(tr("Processes"));
+                            ui.strong(tr("CPU"));
+                            ui.strong(tr("Memory"));
+                            ui.end_row();
+
+                            for user in &diff.users {
+                                ui.label(&user.user);
+                                ui.label(format!("{} -> {}", user.count_before, user.count_after));
+                                ui.label(fmt.cpu_delta(user.cpu_delta(), *cores));
+                                ui.label(fmt.memory_delta(user.mem_delta()));
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        dialog.open = open;
+    }
+}
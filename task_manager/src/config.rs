@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+use std::env::var;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// A flat `key = value` file in $XDG_CONFIG_HOME/task_manager/config (or ~/.config).
+pub struct Config {
+    values: BTreeMap<String, String>,
+    path: Option<PathBuf>,
+}
+
+fn config_path() -> Option<PathBuf> {
+    let base = match var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(var("HOME").ok()?).join(".config"),
+    };
+
+    Some(base.join("task_manager").join("config"))
+}
+
+impl Config {
+    pub fn load() -> Self {
+        let path = config_path();
+        let mut values = BTreeMap::new();
+
+        if let Some(contents) = path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            for line in contents.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some((key, value)) = line.split_once('=') {
+                    values.insert(key.trim().to_string(), value.trim().to_string());
+                }
+            }
+        }
+
+        Self { values, path }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no config directory"));
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut contents = String::new();
+        for (key, value) in &self.values {
+            contents.push_str(&format!("{} = {}\n", key, value));
+        }
+
+        fs::write(path, contents)
+    }
+}
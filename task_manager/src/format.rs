@@ -0,0 +1,115 @@
+use crate::i18n::number;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum UnitSystem {
+    Binary,
+    Si,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum MemoryDisplay {
+    Absolute,
+    Percentage,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum CpuDisplay {
+    PerCore,
+    Normalized,
+}
+
+#[derive(Clone, Copy)]
+pub struct FormatOptions {
+    pub units: UnitSystem,
+    pub memory: MemoryDisplay,
+    pub cpu: CpuDisplay,
+    pub mem_precision: usize,
+    pub cpu_precision: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            units: UnitSystem::Binary,
+            memory: MemoryDisplay::Absolute,
+            cpu: CpuDisplay::Normalized,
+            mem_precision: 1,
+            cpu_precision: 2,
+        }
+    }
+}
+
+impl UnitSystem {
+    fn base(self) -> f64 {
+        match self {
+            UnitSystem::Binary => 1024.0,
+            UnitSystem::Si => 1000.0,
+        }
+    }
+
+    fn suffixes(self) -> [&'static str; 5] {
+        match self {
+            UnitSystem::Binary => ["B", "KiB", "MiB", "GiB", "TiB"],
+            UnitSystem::Si => ["B", "kB", "MB", "GB", "TB"],
+        }
+    }
+}
+
+// Picks the largest unit that keeps the value >= 1, so 1536 bytes becomes "1.5 KiB".
+pub fn scale_bytes(bytes: u64, units: UnitSystem, precision: usize) -> String {
+    let suffixes = units.suffixes();
+    let base = units.base();
+    let mut value = bytes as f64;
+    let mut index = 0;
+
+    while value >= base && index < suffixes.len() - 1 {
+        value /= base;
+        index += 1;
+    }
+
+    if index == 0 {
+        format!("{} {}", bytes, suffixes[0])
+    } else {
+        format!("{} {}", number(value, precision), suffixes[index])
+    }
+}
+
+impl FormatOptions {
+    pub fn memory(&self, bytes: u64, total: u64) -> String {
+        match self.memory {
+            MemoryDisplay::Absolute => scale_bytes(bytes, self.units, self.mem_precision),
+            MemoryDisplay::Percentage => {
+                let percent = if total == 0 {
+                    0.0
+                } else {
+                    bytes as f64 / total as f64 * 100.0
+                };
+                format!("{}%", number(percent, self.mem_precision))
+            }
+        }
+    }
+
+    // `usage` is what sysinfo reports: 100% means one fully busy core.
+    pub fn cpu(&self, usage: f32, cores: usize) -> String {
+        let value = match self.cpu {
+            CpuDisplay::PerCore => usage,
+            CpuDisplay::Normalized => usage / cores.max(1) as f32,
+        };
+
+        format!("{}%", number(value as f64, self.cpu_precision))
+    }
+
+    pub fn memory_delta(&self, delta: i64) -> String {
+        let sign = if delta < 0 { "-" } else { "+" };
+        format!(
+            "{}{}",
+            sign,
+            scale_bytes(delta.unsigned_abs(), self.units, self.mem_precision)
+        )
+    }
+
+    pub fn cpu_delta(&self, delta: f32, cores: usize) -> String {
+        let sign = if delta < 0.0 { "-" } else { "+" };
+        format!("{}{}", sign, self.cpu(delta.abs(), cores))
+    }
+}
@@ -0,0 +1,289 @@
+use std::env::var;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Language {
+    English,
+    Romanian,
+}
+
+pub const LANGUAGES: [Language; 2] = [Language::English, Language::Romanian];
+
+// The language is process wide so free functions and every panel can translate
+// without threading it through, the GUI sets it once at startup and on change.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+impl Language {
+    // Each language is listed under its own name so it can be found without reading the current one.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Romanian => "Română",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Romanian => "ro",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Language> {
+        LANGUAGES.into_iter().find(|l| l.key() == key)
+    }
+
+    // Same precedence as gettext: LC_ALL, then LC_MESSAGES, then LANG, e.g. `ro_RO.UTF-8`.
+    pub fn from_env() -> Language {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Language::from_key(value.get(..2)?))
+            .unwrap_or(Language::English)
+    }
+
+    fn separators(self) -> (char, char) {
+        match self {
+            Language::English => ('.', ','),
+            Language::Romanian => (',', '.'),
+        }
+    }
+}
+
+pub fn set_language(language: Language) {
+    CURRENT.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    LANGUAGES
+        .get(CURRENT.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or(Language::English)
+}
+
+// The English text is the message key, anything missing from a catalog falls back to it.
+pub fn tr(text: &'static str) -> &'static str {
+    match language() {
+        Language::English => text,
+        Language::Romanian => romanian(text).unwrap_or(text),
+    }
+}
+
+// Translates `text` and fills its `{}` placeholders in order.
+pub fn trf(text: &'static str, args: &[&dyn Display]) -> String {
+    let mut res = String::new();
+    let mut args = args.iter();
+    let mut parts = tr(text).split("{}");
+
+    if let Some(first) = parts.next() {
+        res.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            res.push_str(&arg.to_string());
+        }
+        res.push_str(part);
+    }
+
+    res
+}
+
+// Fixed point with the language's decimal mark, digits are grouped from 10 000 up
+// so short values like 1023.5 stay compact.
+pub fn number(value: f64, precision: usize) -> String {
+    let (decimal, group) = language().separators();
+    let text = format!("{:.*}", precision, value.abs());
+    let (whole, fraction) = match text.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (text.as_str(), None),
+    };
+
+    let mut res = String::new();
+    if value.is_sign_negative() && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        res.push('-');
+    }
+    for (i, digit) in whole.chars().enumerate() {
+        if whole.len() > 4 && i > 0 && (whole.len() - i) % 3 == 0 {
+            res.push(group);
+        }
+        res.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        res.push(decimal);
+        res.push_str(fraction);
+    }
+
+    res
+}
+
+fn romanian(text: &str) -> Option<&'static str> {
+    Some(match text {
+        "AICI" => "Manager de activități",
+        "Hello from application" => "Salut din aplicație",
+        "Sort by:" => "Sortează după:",
+        "Name" => "Nume",
+        "Shown: All processes" => "Afișate: Toate procesele",
+        "Shown: User processes" => "Afișate: Procesele utilizatorului",
+        "Shown: System processes" => "Afișate: Procesele sistemului",
+        "All processes" => "Toate procesele",
+        "User processes" => "Procesele utilizatorului",
+        "System processes" => "Procesele sistemului",
+        "Table" => "Tabel",
+        "Tree" => "Arbore",
+        "Threads" => "Fire de execuție",
+        "Sensors" => "Senzori",
+        "Display" => "Afișare",
+        "Memory" => "Memorie",
+        "Absolute" => "Absolut",
+        "% of total" => "% din total",
+        "Binary (KiB, MiB, GiB)" => "Binar (KiB, MiB, GiB)",
+        "decimals" => "zecimale",
+        "Normalized (% of all cores)" => "Normalizat (% din toate nucleele)",
+        "Per core (100% = one core)" => "Pe nucleu (100% = un nucleu)",
+        "Frame metrics (F12)" => "Metrici de cadre (F12)",
+        "Find holder" => "Găsește deținătorul",
+        "Snapshots" => "Instantanee",
+        "Username" => "Utilizator",
+        "Path" => "Cale",
+        "User" => "Utilizator",
+        "Users" => "Utilizatori",
+        "Processes" => "Procese",
+        "Performance" => "Performanță",
+        "Events" => "Evenimente",
+        "Details" => "Detalii",
+        "Suspicious" => "Suspecte",
+        "Services" => "Servicii",
+        "Tab" => "Filă",
+        "Docked" => "Andocat",
+        "Window" => "Fereastră",
+        "Hidden" => "Ascuns",
+        "Layout" => "Aranjament",
+        "Language" => "Limbă",
+        "Every panel is popped out or hidden. Use the Layout menu to bring one back." => "Toate panourile sunt detașate sau ascunse. Folosiți meniul Aranjament pentru a readuce unul.",
+        "Select a process in the Table or Tree view to list its threads." => "Selectați un proces în vizualizarea Tabel sau Arbore pentru a-i lista firele de execuție.",
+        "{} threads of process {}" => "{} fire de execuție ale procesului {}",
+        "State" => "Stare",
+        "Last CPU" => "Ultimul CPU",
+        "Affinity" => "Afinitate",
+        "Temperatures" => "Temperaturi",
+        "No temperature sensors are exposed on this machine." => "Această mașină nu expune senzori de temperatură.",
+        "Sensor" => "Senzor",
+        "Current" => "Curent",
+        "Critical" => "Critic",
+        "History" => "Istoric",
+        "Power supplies" => "Surse de alimentare",
+        "No battery or power supply information available." => "Nu sunt disponibile informații despre baterie sau alimentare.",
+        "Type" => "Tip",
+        "Status" => "Stare",
+        "Charge" => "Încărcare",
+        "Power" => "Putere",
+        "Online" => "Conectat",
+        "Offline" => "Deconectat",
+        "Top " => "Primele ",
+        "Pin" => "Fixează",
+        "Expand" => "Extinde",
+        "File" => "Fișier",
+        "Search" => "Caută",
+        "invalid port: {}" => "port invalid: {}",
+        "What" => "Ce",
+        "Clear" => "Golește",
+        "Time (UTC)" => "Ora (UTC)",
+        "Event" => "Eveniment",
+        "started" => "pornit",
+        "exited" => "încheiat",
+        "Select a process in the Processes panel to see its details." => "Selectați un proces în panoul Procese pentru a-i vedea detaliile.",
+        "Process {} is no longer running." => "Procesul {} nu mai rulează.",
+        "Parent PID" => "PID părinte",
+        "Children" => "Copii",
+        "{} across {} cores" => "{} pe {} nuclee",
+        "{} used of {}" => "{} folosiți din {}",
+        "Window: " => "Fereastră: ",
+        " samples" => " eșantioane",
+        "CPU above: " => "CPU peste: ",
+        "Memory growing on every one of the last {} samples, or CPU above {}% for all of them." => "Memorie în creștere la fiecare dintre ultimele {} eșantioane sau CPU peste {}% la toate.",
+        "Nothing suspicious so far." => "Nimic suspect până acum.",
+        "Reason" => "Motiv",
+        "Growth" => "Creștere",
+        "Avg CPU" => "CPU mediu",
+        "Trend" => "Tendință",
+        "memory growth" => "creștere a memoriei",
+        "high CPU" => "CPU ridicat",
+        "memory and CPU" => "memorie și CPU",
+        "Running units only" => "Doar unitățile active",
+        "No systemd units found on this machine." => "Nu s-au găsit unități systemd pe această mașină.",
+        "Unit" => "Unitate",
+        "Enabled" => "Activată",
+        "Main PID" => "PID principal",
+        "Description" => "Descriere",
+        "running" => "rulează",
+        "inactive" => "inactivă",
+        "yes" => "da",
+        "no" => "nu",
+        "Show in process tree" => "Arată în arborele de procese",
+        "Signal" => "Semnal",
+        "Subtree, children first" => "Subarbore, întâi copiii",
+        "Subtree, parent first" => "Subarbore, întâi părintele",
+        "Process group" => "Grup de procese",
+        "Session" => "Sesiune",
+        "Everything matching the filter" => "Tot ce corespunde filtrului",
+        "subtree of {} ({})" => "subarborele lui {} ({})",
+        "process group {}" => "grupul de procese {}",
+        "process group of {}" => "grupul de procese al lui {}",
+        "session {}" => "sesiunea {}",
+        "session of {}" => "sesiunea lui {}",
+        "every process matching the current filter" => "fiecare proces care corespunde filtrului curent",
+        "The selected process is no longer running." => "Procesul selectat nu mai rulează.",
+        "Unknown" => "Necunoscut",
+        "Confirm signal" => "Confirmă semnalul",
+        "Send {} to {}: {} processes" => "Trimite {} către {}: {} procese",
+        "Send" => "Trimite",
+        "Cancel" => "Anulează",
+        "{} sent to {} of {} processes" => "{} trimis către {} din {} procese",
+        "{}: exited or replaced since the preview, skipped" => "{}: s-a încheiat sau a fost înlocuit de la previzualizare, omis",
+        "Signal result" => "Rezultatul semnalului",
+        "Close" => "Închide",
+        "Scheduling" => "Planificare",
+        "Couldn't read the scheduling of {}: {}" => "Nu s-a putut citi planificarea pentru {}: {}",
+        "Apply" => "Aplică",
+        "Affinity set to {}" => "Afinitate setată la {}",
+        "Policy" => "Politică",
+        "priority " => "prioritate ",
+        "Policy set to {}" => "Politică setată la {}",
+        "Nice set to {}" => "Nice setat la {}",
+        "I/O priority" => "Prioritate I/O",
+        "level " => "nivel ",
+        "I/O priority set to {}" => "Prioritate I/O setată la {}",
+        "Save current to" => "Salvează starea curentă în",
+        "Save" => "Salvează",
+        "Saved {}" => "Salvat {}",
+        "Couldn't save: {}" => "Nu s-a putut salva: {}",
+        "Before" => "Înainte",
+        "After" => "După",
+        "Largest change in" => "Cea mai mare schimbare la",
+        "Compare" => "Compară",
+        "Before: {}" => "Înainte: {}",
+        "After: {}" => "După: {}",
+        "Change" => "Schimbare",
+        "added" => "adăugat",
+        "removed" => "eliminat",
+        "changed" => "modificat",
+        "Running" => "Rulează",
+        "Sleeping" => "Doarme",
+        "Disk sleep" => "Așteaptă discul",
+        "Stopped" => "Oprit",
+        "Tracing stop" => "Oprit pentru depanare",
+        "Zombie" => "Zombi",
+        "Dead" => "Mort",
+        "Idle" => "Inactiv",
+        "Command line" => "Linie de comandă",
+        "Virtual memory" => "Memorie virtuală",
+        "Page faults" => "Erori de pagină",
+        "{} minor, {} major" => "{} minore, {} majore",
+        "Disk read" => "Citit de pe disc",
+        "Disk written" => "Scris pe disc",
+        "not permitted" => "nepermis",
+        _ => return None,
+    })
+}
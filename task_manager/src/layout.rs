@@ -0,0 +1,167 @@
+use crate::config::Config;
+use crate::i18n::tr;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Panel {
+    Processes,
+    Performance,
+    Users,
+    Events,
+    Details,
+    Suspicious,
+    Services,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Placement {
+    Tab,
+    Docked,
+    Window,
+    Hidden,
+}
+
+pub const PANELS: [Panel; 7] = [
+    Panel::Processes,
+    Panel::Performance,
+    Panel::Users,
+    Panel::Events,
+    Panel::Details,
+    Panel::Suspicious,
+    Panel::Services,
+];
+
+impl Panel {
+    pub fn key(self) -> &'static str {
+        match self {
+            Panel::Processes => "processes",
+            Panel::Performance => "performance",
+            Panel::Users => "users",
+            Panel::Events => "events",
+            Panel::Details => "details",
+            Panel::Suspicious => "suspicious",
+            Panel::Services => "services",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        tr(match self {
+            Panel::Processes => "Processes",
+            Panel::Performance => "Performance",
+            Panel::Users => "Users",
+            Panel::Events => "Events",
+            Panel::Details => "Details",
+            Panel::Suspicious => "Suspicious",
+            Panel::Services => "Services",
+        })
+    }
+
+    fn from_key(key: &str) -> Option<Panel> {
+        PANELS.into_iter().find(|p| p.key() == key)
+    }
+}
+
+impl Placement {
+    pub fn label(self) -> &'static str {
+        tr(match self {
+            Placement::Tab => "Tab",
+            Placement::Docked => "Docked",
+            Placement::Window => "Window",
+            Placement::Hidden => "Hidden",
+        })
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Placement::Tab => "tab",
+            Placement::Docked => "docked",
+            Placement::Window => "window",
+            Placement::Hidden => "hidden",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Placement> {
+        [
+            Placement::Tab,
+            Placement::Docked,
+            Placement::Window,
+            Placement::Hidden,
+        ]
+        .into_iter()
+        .find(|p| p.key() == key)
+    }
+}
+
+pub struct Layout {
+    placements: [Placement; PANELS.len()],
+    pub active: Panel,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            placements: [Placement::Tab; PANELS.len()],
+            active: Panel::Processes,
+        }
+    }
+}
+
+impl Layout {
+    pub fn load(config: &Config) -> Self {
+        let mut layout = Layout::default();
+
+        for (i, panel) in PANELS.into_iter().enumerate() {
+            let key = format!("layout.{}", panel.key());
+            if let Some(placement) = config.get(&key).and_then(Placement::from_key) {
+                layout.placements[i] = placement;
+            }
+        }
+
+        if let Some(active) = config.get("layout.active").and_then(Panel::from_key) {
+            layout.active = active;
+        }
+
+        layout
+    }
+
+    pub fn store(&self, config: &mut Config) {
+        for (i, panel) in PANELS.into_iter().enumerate() {
+            config.set(&format!("layout.{}", panel.key()), self.placements[i].key());
+        }
+        config.set("layout.active", self.active.key());
+    }
+
+    pub fn placement(&self, panel: Panel) -> Placement {
+        self.placements[panel as usize]
+    }
+
+    pub fn set_placement(&mut self, panel: Panel, placement: Placement) {
+        self.placements[panel as usize] = placement;
+    }
+
+    pub fn with(&self, placement: Placement) -> Vec<Panel> {
+        PANELS
+            .into_iter()
+            .filter(|p| self.placement(*p) == placement)
+            .collect()
+    }
+
+    // The active tab (falling back to the first tab) followed by every docked panel.
+    pub fn shown(&self) -> Vec<Panel> {
+        let tabs = self.with(Placement::Tab);
+        let mut shown = Vec::new();
+
+        if tabs.contains(&self.active) {
+            shown.push(self.active);
+        } else if let Some(first) = tabs.first() {
+            shown.push(*first);
+        }
+
+        shown.append(&mut self.with(Placement::Docked));
+        shown
+    }
+
+    // Shown as a tab or docked, or in its own window.
+    pub fn is_visible(&self, panel: Panel) -> bool {
+        self.placement(panel) == Placement::Window || self.shown().contains(&panel)
+    }
+}
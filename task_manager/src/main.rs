@@ -1,10 +1,38 @@
+mod actions;
 mod backend;
-use crate::backend::gatherer::{InfoGetter, Monitor, ProcessInfo, SysStats};
-use ::std::sync::mpsc::{self, Receiver};
-use ::std::{cmp::Ordering, collections::HashSet, env::var, thread, thread::sleep, time};
-use eframe::egui::{self, CentralPanel, Context, FontFamily, FontId, TextStyle};
+mod cli;
+mod compare;
+mod config;
+mod format;
+mod i18n;
+mod layout;
+mod panels;
+mod query;
+mod view;
+use crate::actions::{SchedEditor, SignalState};
+use crate::compare::CompareDialog;
+use crate::backend::detector::{Detector, DetectorSettings};
+use crate::backend::gatherer::{Backend, InfoGetter, Monitor, ProcessInfo, SysStats};
+use crate::backend::lookup::{self, Holder};
+use crate::backend::procfs::ProcMonitor;
+use crate::backend::sensors::SensorStats;
+use crate::backend::services::{Service, ServiceScanner};
+use crate::backend::threads::{self, ThreadInfo, ThreadSampler};
+use crate::config::Config;
+use crate::format::{CpuDisplay, FormatOptions, MemoryDisplay, UnitSystem};
+use crate::i18n::{number, set_language, tr, trf, Language};
+use crate::layout::Layout;
+use crate::panels::ProcessEvent;
+use crate::view::{Row, ViewCache};
+use ::std::sync::mpsc::{self, Receiver, Sender};
+use ::std::collections::{HashMap, HashSet, VecDeque};
+use ::std::time::Instant;
+use ::std::{cmp::Ordering, env, env::var, path::Path, thread, thread::sleep, time};
+use eframe::egui::{self, CentralPanel, Context, FontFamily, FontId, TextStyle, TopBottomPanel};
 use egui_extras::{Column, TableBuilder};
 
+const TITLE: &str = "AICI";
+
 #[derive(PartialEq, Clone, Copy)]
 enum SortCriteria {
     Cpu,
@@ -29,6 +57,22 @@ enum SortType {
 enum ViewType {
     Table,
     Tree,
+    Threads,
+    Sensors,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum LookupKind {
+    File,
+    Port,
+}
+
+struct LookupDialog {
+    open: bool,
+    kind: LookupKind,
+    query: String,
+    results: Vec<Holder>,
+    error: Option<String>,
 }
 
 struct TaskManager {
@@ -40,6 +84,29 @@ struct TaskManager {
     user: String,
     view_type: ViewType,
     open: HashSet<u32>,
+    format: FormatOptions,
+    compact: bool,
+    top_n: usize,
+    threshold: f32,
+    on_top: bool,
+    lookup: LookupDialog,
+    config: Config,
+    layout: Layout,
+    selected: Option<u32>,
+    cpu_history: VecDeque<f32>,
+    mem_history: VecDeque<f32>,
+    events: VecDeque<ProcessEvent>,
+    known: HashMap<u32, String>,
+    sampler: ThreadSampler,
+    threads: Vec<ThreadInfo>,
+    signals: SignalState,
+    sched: SchedEditor,
+    compare: CompareDialog,
+    detector: Detector,
+    services: Vec<Service>,
+    service_scanner: ServiceScanner,
+    running_only: bool,
+    view: ViewCache,
 }
 
 impl TaskManager {
@@ -85,10 +152,10 @@ impl TaskManager {
                 a.memory.partial_cmp(&b.memory).unwrap_or(Ordering::Equal)
             }
             (SortCriteria::Name, SortType::Ascending) => {
-                b.name.partial_cmp(&a.name).unwrap_or(Ordering::Equal)
+                a.name.partial_cmp(&b.name).unwrap_or(Ordering::Equal)
             }
             (SortCriteria::Name, SortType::Descending) => {
-                a.name.partial_cmp(&b.name).unwrap_or(Ordering::Equal)
+                b.name.partial_cmp(&a.name).unwrap_or(Ordering::Equal)
             }
         });
 
@@ -141,10 +208,10 @@ impl TaskManager {
                     a.memory.partial_cmp(&b.memory).unwrap_or(Ordering::Equal)
                 }
                 (SortCriteria::Name, SortType::Ascending) => {
-                    b.name.partial_cmp(&a.name).unwrap_or(Ordering::Equal)
+                    a.name.partial_cmp(&b.name).unwrap_or(Ordering::Equal)
                 }
                 (SortCriteria::Name, SortType::Descending) => {
-                    a.name.partial_cmp(&b.name).unwrap_or(Ordering::Equal)
+                    b.name.partial_cmp(&a.name).unwrap_or(Ordering::Equal)
                 }
             });
 
@@ -205,16 +272,12 @@ impl TaskManager {
 
         view
     }
-    fn table_drawer(ui: &mut egui::Ui, stats: &SysStats, crit: SortCriteria, sort_type: SortType, filter: FilterType, username: &String) {
+    fn table_drawer(&mut self, ui: &mut egui::Ui) {
         let width = ui.available_width();
-
-        let viewer = TaskManager::data_table_view(
-                &stats.processes,
-                crit,
-                sort_type,
-                filter,
-                username,
-            );
+        let stats = &self.stats;
+        let fmt = &self.format;
+        let selected = &mut self.selected;
+        let viewer = &self.view.rows;
 
         TableBuilder::new(ui)
             .vscroll(true)
@@ -225,32 +288,32 @@ impl TaskManager {
             .column(Column::initial(width * 0.15).resizable(true))
             .header(20.0, |mut header| {
                 header.col(|ui| {
-                    ui.heading("Name");
+                    ui.heading(tr("Name"));
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("CPU");
-                        ui.label(format!("{:.1}%", stats.cpu));
+                        ui.heading(tr("CPU"));
+                        ui.label(fmt.cpu(stats.cpu * stats.cores as f32, stats.cores));
                     });
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("Memory");
-                        ui.label(format!("{:.1}%", stats.mem));
+                        ui.heading(tr("Memory"));
+                        ui.label(fmt.memory(stats.used_mem, stats.total_mem));
                     });
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("Path");
+                        ui.heading(tr("Path"));
                     });
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("Username");
+                        ui.heading(tr("Username"));
                     });
                     ui.separator();
                 });
@@ -261,41 +324,43 @@ impl TaskManager {
 
                 body.rows(height, num, |mut row| {
                     let index = row.index();
-                    let process = viewer[index];
+                    let process = &viewer[index];
 
                     row.col(|ui| {
-                        ui.label(process.name.to_string());
+                        if ui
+                            .selectable_label(*selected == Some(process.pid), &process.name)
+                            .clicked()
+                        {
+                            *selected = Some(process.pid);
+                        }
                     });
 
                     row.col(|ui| {
-                        ui.label(format!("{:.2}%", process.cpu));
+                        ui.label(fmt.cpu(process.cpu, stats.cores));
                     });
 
                     row.col(|ui| {
-                        ui.label(format!("{:.1} MB", process.memory));
+                        ui.label(fmt.memory(process.memory, stats.total_mem));
                     });
 
                     row.col(|ui| {
-                        ui.label(process.exe.to_string());
+                        ui.label(&process.exe);
                     });
 
                     row.col(|ui| {
-                        ui.label(process.user.to_string());
+                        ui.label(&process.user);
                     });
                 });
             });
     }
-    fn tree_drawer(ui: &mut egui::Ui, stats: &SysStats,  crit: SortCriteria, sort_type: SortType, filter: FilterType, username: &String, open: &mut HashSet<u32>) {
+    fn tree_drawer(&mut self, ui: &mut egui::Ui) {
         let width = ui.available_width();
-
-        let viewer = TaskManager::data_tree_view(
-                &stats.processes,
-                crit,
-                sort_type,
-                filter,
-                username,
-                open
-            );
+        let stats = &self.stats;
+        let fmt = &self.format;
+        let open = &mut self.open;
+        let selected = &mut self.selected;
+        let viewer = &self.view.rows;
+        let mut toggled = false;
 
         TableBuilder::new(ui)
             .vscroll(true)
@@ -306,32 +371,32 @@ impl TaskManager {
             .column(Column::initial(width * 0.15).resizable(true))
             .header(20.0, |mut header| {
                 header.col(|ui| {
-                    ui.heading("Name");
+                    ui.heading(tr("Name"));
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("CPU");
-                        ui.label(format!("{:.1}%", stats.cpu));
+                        ui.heading(tr("CPU"));
+                        ui.label(fmt.cpu(stats.cpu * stats.cores as f32, stats.cores));
                     });
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("Memory");
-                        ui.label(format!("{:.1}%", stats.mem));
+                        ui.heading(tr("Memory"));
+                        ui.label(fmt.memory(stats.used_mem, stats.total_mem));
                     });
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("Path");
+                        ui.heading(tr("Path"));
                     });
                     ui.separator();
                 });
                 header.col(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.heading("Username");
+                        ui.heading(tr("Username"));
                     });
                     ui.separator();
                 });
@@ -342,12 +407,12 @@ impl TaskManager {
 
                 body.rows(height, num, |mut row| {
                     let index = row.index();
-                    let (process, depth) = viewer[index];
+                    let process = &viewer[index];
 
                     row.col(|ui| {
                         ui.horizontal(|ui| {
-                            ui.add_space(depth as f32 * 20.0);
-                            if !process.child.is_empty() {
+                            ui.add_space(process.depth as f32 * 20.0);
+                            if process.has_children {
                                 let arrow = if open.contains(&process.pid) {
                                     "v"
                                 }
@@ -362,60 +427,520 @@ impl TaskManager {
                                     else {
                                         open.insert(process.pid);
                                     }
+                                    toggled = true;
                                 }
                             }
                             else {
                                 ui.add_space(20.0);
                             }
 
-                            ui.label(process.name.to_string());
+                            if ui
+                                .selectable_label(*selected == Some(process.pid), &process.name)
+                                .clicked()
+                            {
+                                *selected = Some(process.pid);
+                            }
                         });
                     });
 
                     row.col(|ui| {
-                        ui.label(format!("{:.2}%", process.cpu));
+                        ui.label(fmt.cpu(process.cpu, stats.cores));
                     });
 
                     row.col(|ui| {
-                        ui.label(format!("{:.1} MB", process.memory));
+                        ui.label(fmt.memory(process.memory, stats.total_mem));
                     });
 
                     row.col(|ui| {
-                        ui.label(process.exe.to_string());
+                        ui.label(&process.exe);
                     });
 
                     row.col(|ui| {
-                        ui.label(process.user.to_string());
+                        ui.label(&process.user);
                     });
                 });
             });
+
+        if toggled {
+            self.view.dirty = true;
+        }
     }
-}
+    fn threads_drawer(&self, ui: &mut egui::Ui) {
+        let Some(pid) = self.selected else {
+            ui.label(tr("Select a process in the Table or Tree view to list its threads."));
+            return;
+        };
 
-impl Default for TaskManager {
-    fn default() -> Self {
-        let (tx, rx) = mpsc::channel();
+        ui.label(trf("{} threads of process {}", &[&self.threads.len(), &pid]));
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("threads")
+                .num_columns(6)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(tr("TID"));
+                    ui.strong(tr("Name"));
+                    ui.strong(tr("State"));
+                    ui.strong(tr("CPU"));
+                    ui.strong(tr("Last CPU"));
+                    ui.strong(tr("Affinity"));
+                    ui.end_row();
+
+                    for thread in &self.threads {
+                        ui.label(thread.tid.to_string());
+                        ui.label(&thread.name);
+                        ui.label(tr(threads::state_name(thread.state)));
+                        ui.label(self.format.cpu(thread.cpu, self.stats.cores));
+                        ui.label(match thread.processor {
+                            Some(cpu) => cpu.to_string(),
+                            None => "-".to_string(),
+                        });
+                        ui.label(&thread.affinity);
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+    fn sensors_drawer(&self, ui: &mut egui::Ui) {
+        let sensors = &self.stats.sensors;
+        let celsius = |t: Option<f32>| match t {
+            Some(t) => format!("{} °C", number(t as f64, 1)),
+            None => "-".to_string(),
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading(tr("Temperatures"));
+            if sensors.readings.is_empty() {
+                ui.label(tr("No temperature sensors are exposed on this machine."));
+            } else {
+                egui::Grid::new("temperatures")
+                    .num_columns(6)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong(tr("Sensor"));
+                        ui.strong(tr("Current"));
+                        ui.strong(tr("Min"));
+                        ui.strong(tr("Max"));
+                        ui.strong(tr("Critical"));
+                        ui.strong(tr("History"));
+                        ui.end_row();
+
+                        for reading in &sensors.readings {
+                            ui.label(&reading.label);
+                            match (reading.temperature, reading.critical) {
+                                (Some(t), Some(crit)) if t >= crit => {
+                                    ui.colored_label(egui::Color32::RED, celsius(Some(t)))
+                                }
+                                (t, _) => ui.label(celsius(t)),
+                            };
+                            ui.label(celsius(reading.min));
+                            ui.label(celsius(reading.max));
+                            ui.label(celsius(reading.critical));
+
+                            let values: Vec<f32> = reading.history.iter().copied().collect();
+                            let top = reading.critical.or(reading.max).unwrap_or(100.0);
+                            sparkline(ui, &values, top, egui::vec2(120.0, 24.0));
+                            ui.end_row();
+                        }
+                    });
+            }
+
+            ui.separator();
+            ui.heading(tr("Power supplies"));
+            if sensors.supplies.is_empty() {
+                ui.label(tr("No battery or power supply information available."));
+            } else {
+                egui::Grid::new("power_supplies")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong(tr("Name"));
+                        ui.strong(tr("Type"));
+                        ui.strong(tr("Status"));
+                        ui.strong(tr("Charge"));
+                        ui.strong(tr("Power"));
+                        ui.end_row();
+
+                        for supply in &sensors.supplies {
+                            ui.label(&supply.name);
+                            ui.label(&supply.kind);
+
+                            let status = match (&supply.status, supply.online) {
+                                (Some(status), _) => status.clone(),
+                                (None, Some(true)) => tr("Online").to_string(),
+                                (None, Some(false)) => tr("Offline").to_string(),
+                                (None, None) => "-".to_string(),
+                            };
+                            ui.label(status);
+
+                            match supply.capacity {
+                                Some(capacity) => ui.add(
+                                    egui::ProgressBar::new(capacity as f32 / 100.0)
+                                        .desired_width(120.0)
+                                        .text(format!("{}%", capacity)),
+                                ),
+                                None => ui.label("-"),
+                            };
+
+                            match supply.power_watts {
+                                Some(watts) => ui.label(format!("{} W", number(watts, 1))),
+                                None => ui.label("-"),
+                            };
+                            ui.end_row();
+                        }
+                    });
+            }
+        });
+    }
+    fn processes_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading(tr("Hello from application"));
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            let arrow = if self.sort_type == SortType::Ascending {
+                "^"
+            } else {
+                "v"
+            };
+            ui.label(tr("Sort by:"));
+            let cpu_label = match self.criteria {
+                SortCriteria::Cpu => format!("{} CPU", arrow),
+                _ => "CPU".to_string(),
+            };
+            let mem_label = match self.criteria {
+                SortCriteria::Memory => format!("{} RAM", arrow),
+                _ => "RAM".to_string(),
+            };
+            let name_label = match self.criteria {
+                SortCriteria::Name => format!("{} {}", arrow, tr("Name")),
+                _ => tr("Name").to_string(),
+            };
+            if ui
+                .selectable_label(self.criteria == SortCriteria::Cpu, cpu_label)
+                .clicked()
+            {
+                self.criteria = SortCriteria::Cpu;
+                match self.sort_type {
+                    SortType::Ascending => self.sort_type = SortType::Descending,
+                    SortType::Descending => self.sort_type = SortType::Ascending,
+                };
+            };
+
+            if ui
+                .selectable_label(self.criteria == SortCriteria::Memory, mem_label)
+                .clicked()
+            {
+                self.criteria = SortCriteria::Memory;
+                match self.sort_type {
+                    SortType::Ascending => self.sort_type = SortType::Descending,
+                    SortType::Descending => self.sort_type = SortType::Ascending,
+                };
+            }
+
+            if ui
+                .selectable_label(self.criteria == SortCriteria::Name, name_label)
+                .clicked()
+            {
+                self.criteria = SortCriteria::Name;
+                match self.sort_type {
+                    SortType::Ascending => self.sort_type = SortType::Descending,
+                    SortType::Descending => self.sort_type = SortType::Ascending,
+                };
+            }
+
+            let filter = match self.filter {
+                FilterType::All => tr("Shown: All processes"),
+                FilterType::User => tr("Shown: User processes"),
+                FilterType::System => tr("Shown: System processes"),
+            };
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                egui::ComboBox::from_label("")
+                    .selected_text(filter)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.filter, FilterType::All, tr("All processes"));
+                        ui.selectable_value(
+                            &mut self.filter,
+                            FilterType::User,
+                            tr("User processes"),
+                        );
+                        ui.selectable_value(
+                            &mut self.filter,
+                            FilterType::System,
+                            tr("System processes"),
+                        );
+                    });
+
+                if ui
+                    .selectable_label(self.view_type == ViewType::Table, tr("Table"))
+                    .clicked()
+                {
+                    self.view_type = ViewType::Table;
+                }
+
+                if ui
+                    .selectable_label(self.view_type == ViewType::Tree, tr("Tree"))
+                    .clicked()
+                {
+                    self.view_type = ViewType::Tree;
+                }
+
+                if ui
+                    .selectable_label(self.view_type == ViewType::Threads, tr("Threads"))
+                    .clicked()
+                {
+                    self.view_type = ViewType::Threads;
+                }
+
+                if ui
+                    .selectable_label(self.view_type == ViewType::Sensors, tr("Sensors"))
+                    .clicked()
+                {
+                    self.view_type = ViewType::Sensors;
+                }
+
+                ui.menu_button(tr("Display"), |ui| {
+                    ui.label(tr("Memory"));
+                    ui.radio_value(&mut self.format.memory, MemoryDisplay::Absolute, tr("Absolute"));
+                    ui.radio_value(&mut self.format.memory, MemoryDisplay::Percentage, tr("% of total"));
+                    ui.radio_value(&mut self.format.units, UnitSystem::Binary, tr("Binary (KiB, MiB, GiB)"));
+                    ui.radio_value(&mut self.format.units, UnitSystem::Si, tr("SI (kB, MB, GB)"));
+                    ui.add(egui::Slider::new(&mut self.format.mem_precision, 0..=3).text(tr("decimals")));
+                    ui.separator();
+
+                    ui.label(tr("CPU"));
+                    ui.radio_value(&mut self.format.cpu, CpuDisplay::Normalized, tr("Normalized (% of all cores)"));
+                    ui.radio_value(&mut self.format.cpu, CpuDisplay::PerCore, tr("Per core (100% = one core)"));
+                    ui.add(egui::Slider::new(&mut self.format.cpu_precision, 0..=3).text(tr("decimals")));
+                    ui.separator();
+
+                    ui.checkbox(&mut self.view.show_metrics, tr("Frame metrics (F12)"));
+                });
+
+                if ui.button(tr("Compact")).clicked() {
+                    let ctx = ui.ctx().clone();
+                    self.set_compact(&ctx, true);
+                }
+
+                if ui.button(tr("Find holder")).clicked() {
+                    self.lookup.open = true;
+                }
+
+                self.signal_menu(ui);
+
+                if ui.button(tr("Snapshots")).clicked() {
+                    self.compare.open = true;
+                }
+            });
+        });
+
+        // Pick up sort or filter changes made in the toolbar above within the same frame.
+        self.refresh_view();
+
+        match self.view_type {
+            ViewType::Table => self.table_drawer(ui),
+            ViewType::Tree => self.tree_drawer(ui),
+            ViewType::Threads => self.threads_drawer(ui),
+            ViewType::Sensors => self.sensors_drawer(ui),
+        }
+    }
+    fn set_compact(&mut self, ctx: &Context, compact: bool) {
+        self.compact = compact;
+
+        let size = if compact { [320.0, 240.0] } else { [1000.0, 600.0] };
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size.into()));
+    }
+    fn compact_drawer(&mut self, ui: &mut egui::Ui) {
+        let stats = &self.stats;
+        let fmt = &self.format;
+
+        let cpu_fraction = stats.cpu / 100.0;
+        let mem_fraction = if stats.total_mem == 0 {
+            0.0
+        } else {
+            stats.used_mem as f32 / stats.total_mem as f32
+        };
+
+        ui.add(
+            egui::ProgressBar::new(cpu_fraction)
+                .text(format!("CPU {}", fmt.cpu(stats.cpu * stats.cores as f32, stats.cores))),
+        );
+        ui.add(
+            egui::ProgressBar::new(mem_fraction)
+                .text(format!("RAM {}", fmt.memory(stats.used_mem, stats.total_mem))),
+        );
+        ui.separator();
+
+        let viewer = &self.view.rows;
 
-        thread::spawn(move || {
-            let mut monitor = Monitor::new();
+        // The threshold is a percentage of the whole machine for the active criteria.
+        let above = |process: &&Row| match self.criteria {
+            SortCriteria::Cpu => process.cpu / stats.cores.max(1) as f32 >= self.threshold,
+            SortCriteria::Memory => {
+                stats.total_mem != 0
+                    && (process.memory as f64 / stats.total_mem as f64 * 100.0) as f32
+                        >= self.threshold
+            }
+            SortCriteria::Name => true,
+        };
 
-            loop {
-                let processes = monitor.system_info_update();
-                if tx.send(processes).is_err() {
-                    break;
+        egui::Grid::new("compact_top")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for process in viewer.iter().filter(above).take(self.top_n) {
+                    ui.label(&process.name);
+                    ui.label(fmt.cpu(process.cpu, stats.cores));
+                    ui.label(fmt.memory(process.memory, stats.total_mem));
+                    ui.end_row();
                 }
-                sleep(time::Duration::from_millis(1000));
+            });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.top_n).range(1..=50).prefix(tr("Top ")));
+            ui.add(
+                egui::DragValue::new(&mut self.threshold)
+                    .range(0.0..=100.0)
+                    .speed(0.5)
+                    .prefix(">= ")
+                    .suffix("%"),
+            );
+
+            if ui.checkbox(&mut self.on_top, tr("Pin")).changed() {
+                let level = if self.on_top {
+                    egui::WindowLevel::AlwaysOnTop
+                } else {
+                    egui::WindowLevel::Normal
+                };
+                ui.ctx().send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+            }
+
+            if ui.button(tr("Expand")).clicked() {
+                let ctx = ui.ctx().clone();
+                self.set_compact(&ctx, false);
             }
         });
+    }
+    fn lookup_window(&mut self, ctx: &Context) {
+        let dialog = &mut self.lookup;
+        let mut open = dialog.open;
+
+        egui::Window::new(tr("Find holder"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut dialog.kind, LookupKind::Port, tr("Port"));
+                    ui.radio_value(&mut dialog.kind, LookupKind::File, tr("File"));
+                });
+
+                ui.horizontal(|ui| {
+                    let hint = match dialog.kind {
+                        LookupKind::Port => "8080",
+                        LookupKind::File => "/path/to/file",
+                    };
+                    let edit = ui.add(egui::TextEdit::singleline(&mut dialog.query).hint_text(hint));
+                    let enter = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                    if ui.button(tr("Search")).clicked() || enter {
+                        let result = match dialog.kind {
+                            LookupKind::Port => match dialog.query.trim().parse::<u16>() {
+                                Ok(port) => lookup::who_uses_port(port),
+                                Err(e) => Err(std::io::Error::other(trf("invalid port: {}", &[&e]))),
+                            },
+                            LookupKind::File => lookup::who_has_file(Path::new(dialog.query.trim())),
+                        };
+
+                        match result {
+                            Ok(holders) => {
+                                dialog.error = None;
+                                dialog.results = holders;
+                            }
+                            Err(e) => {
+                                dialog.error = Some(e.to_string());
+                                dialog.results.clear();
+                            }
+                        }
+                    }
+                });
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("lookup_results")
+                        .num_columns(4)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong(tr("PID"));
+                            ui.strong(tr("Name"));
+                            ui.strong(tr("FD"));
+                            ui.strong(tr("What"));
+                            ui.end_row();
+
+                            for holder in &dialog.results {
+                                ui.label(holder.pid.to_string());
+                                ui.label(&holder.name);
+                                ui.label(holder.fd.to_string());
+                                ui.label(&holder.what);
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        dialog.open = open;
+    }
+}
+
+fn gather<G: InfoGetter>(tx: Sender<SysStats>) {
+    let mut monitor = G::new();
+
+    loop {
+        let processes = monitor.system_info_update();
+        if tx.send(processes).is_err() {
+            break;
+        }
+        sleep(time::Duration::from_millis(1000));
+    }
+}
+
+impl TaskManager {
+    fn new(backend: Backend) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || match backend {
+            Backend::Sysinfo => gather::<Monitor>(tx),
+            Backend::Proc => gather::<ProcMonitor>(tx),
+        });
 
         let user = var("USER").unwrap_or_else(|_| "unknown".to_string());
+        let config = Config::load();
+        let layout = Layout::load(&config);
+        let defaults = DetectorSettings::default();
+        let detector = Detector::new(DetectorSettings {
+            window: config
+                .get("detector.window")
+                .and_then(|w| w.parse().ok())
+                .unwrap_or(defaults.window),
+            cpu_threshold: config
+                .get("detector.cpu_threshold")
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(defaults.cpu_threshold),
+        });
 
         Self {
             rx,
             stats: SysStats {
                 processes: Vec::new(),
                 cpu: 0.0,
-                mem: 0.0,
+                used_mem: 0,
+                total_mem: 0,
+                cores: 0,
+                sensors: SensorStats::default(),
             },
             criteria: SortCriteria::Cpu,
             sort_type: SortType::Descending,
@@ -423,122 +948,116 @@ impl Default for TaskManager {
             view_type: ViewType::Table,
             user,
             open: HashSet::new(),
+            format: FormatOptions::default(),
+            compact: false,
+            top_n: 5,
+            threshold: 0.0,
+            on_top: false,
+            lookup: LookupDialog {
+                open: false,
+                kind: LookupKind::Port,
+                query: String::new(),
+                results: Vec::new(),
+                error: None,
+            },
+            config,
+            layout,
+            selected: None,
+            cpu_history: VecDeque::new(),
+            mem_history: VecDeque::new(),
+            events: VecDeque::new(),
+            known: HashMap::new(),
+            sampler: ThreadSampler::new(),
+            threads: Vec::new(),
+            signals: SignalState::default(),
+            sched: SchedEditor::default(),
+            compare: CompareDialog::default(),
+            detector,
+            services: Vec::new(),
+            service_scanner: ServiceScanner::new(),
+            running_only: true,
+            view: ViewCache::default(),
         }
     }
 }
 
 impl eframe::App for TaskManager {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+        let frame_start = Instant::now();
+
         if let Ok(data) = self.rx.try_recv() {
             self.stats = data;
-            println!("Refresh done");
+            self.record_refresh();
+            self.view.dirty = true;
+        }
+
+        if ctx.input(|i| i.key_pressed(egui::Key::F12)) {
+            self.view.show_metrics = !self.view.show_metrics;
         }
 
         set_styles(ctx);
-        CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Hello from aplication");
-            ui.separator();
+        self.refresh_view();
 
-            ui.horizontal(|ui| {
-                let arrow = if self.sort_type == SortType::Ascending {
-                    "^"
-                } else {
-                    "v"
-                };
-                ui.label("Sort by:".to_string());
-                let cpu_label = match self.criteria {
-                    SortCriteria::Cpu => format!("{} CPU", arrow),
-                    _ => "CPU".to_string(),
-                };
-                let mem_label = match self.criteria {
-                    SortCriteria::Memory => format!("{} RAM", arrow),
-                    _ => "RAM".to_string(),
-                };
-                let name_label = match self.criteria {
-                    SortCriteria::Name => format!("{} Name", arrow),
-                    _ => "Name".to_string(),
-                };
-                if ui
-                    .selectable_label(self.criteria == SortCriteria::Cpu, cpu_label)
-                    .clicked()
-                {
-                    self.criteria = SortCriteria::Cpu;
-                    match self.sort_type {
-                        SortType::Ascending => self.sort_type = SortType::Descending,
-                        SortType::Descending => self.sort_type = SortType::Ascending,
-                    };
-                };
+        if self.compact {
+            CentralPanel::default().show(ctx, |ui| self.compact_drawer(ui));
+        } else {
+            TopBottomPanel::top("tabs").show(ctx, |ui| self.tab_bar(ui));
 
-                if ui
-                    .selectable_label(self.criteria == SortCriteria::Memory, mem_label)
-                    .clicked()
-                {
-                    self.criteria = SortCriteria::Memory;
-                    match self.sort_type {
-                        SortType::Ascending => self.sort_type = SortType::Descending,
-                        SortType::Descending => self.sort_type = SortType::Ascending,
-                    };
-                }
+            CentralPanel::default().show(ctx, |ui| {
+                let shown = self.layout.shown();
 
-                if ui
-                    .selectable_label(self.criteria == SortCriteria::Name, name_label)
-                    .clicked()
-                {
-                    self.criteria = SortCriteria::Name;
-                    match self.sort_type {
-                        SortType::Ascending => self.sort_type = SortType::Descending,
-                        SortType::Descending => self.sort_type = SortType::Ascending,
-                    };
+                match shown.len() {
+                    0 => {
+                        ui.label(tr("Every panel is popped out or hidden. Use the Layout menu to bring one back."));
+                    }
+                    1 => self.draw_panel(ui, shown[0]),
+                    n => ui.columns(n, |columns| {
+                        for (column, panel) in columns.iter_mut().zip(shown) {
+                            column.push_id(panel.key(), |ui| self.draw_panel(ui, panel));
+                        }
+                    }),
                 }
+            });
 
-                let filter = match self.filter {
-                    FilterType::All => "Shown: All processes".to_string(),
-                    FilterType::User => "Shown: User processes".to_string(),
-                    FilterType::System => "Shown: System processes".to_string(),
-                };
+            self.popped_out_windows(ctx);
 
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    egui::ComboBox::from_label("")
-                        .selected_text(filter)
-                        .show_ui(ui, |ui| {
-                            ui.selectable_value(&mut self.filter, FilterType::All, "All processes");
-                            ui.selectable_value(
-                                &mut self.filter,
-                                FilterType::User,
-                                "User processes",
-                            );
-                            ui.selectable_value(
-                                &mut self.filter,
-                                FilterType::System,
-                                "System processes",
-                            );
-                        });
+            self.lookup_window(ctx);
+            self.signal_window(ctx);
+            self.compare_window(ctx);
+        }
 
-                    if ui
-                        .selectable_label(self.view_type == ViewType::Table, "Table")
-                        .clicked()
-                    {
-                        self.view_type = ViewType::Table;
-                    }
+        self.view.record_frame(frame_start.elapsed());
+        if self.view.show_metrics {
+            self.metrics_overlay(ctx);
+        }
 
-                    if ui
-                        .selectable_label(self.view_type == ViewType::Tree, "Tree")
-                        .clicked()
-                    {
-                        self.view_type = ViewType::Tree;
-                    }
-                });
-            });
+        ctx.request_repaint_after(time::Duration::from_millis(1000));
+    }
+}
 
-            if self.view_type == ViewType::Table {
-                Self::table_drawer(ui, &self.stats, self.criteria, self.sort_type, self.filter, &self.user);
-            } else {
-                Self::tree_drawer(ui, &self.stats, self.criteria, self.sort_type, self.filter, &self.user, &mut self.open);
-            }
-        });
+fn sparkline(ui: &mut egui::Ui, values: &[f32], top: f32, size: egui::Vec2) {
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_stroke(rect, 2.0, ui.visuals().widgets.noninteractive.bg_stroke);
 
-        ctx.request_repaint_after(time::Duration::from_millis(1000));
+    if values.len() < 2 || top <= 0.0 {
+        return;
     }
+
+    let step = rect.width() / (values.len() - 1) as f32;
+    let points: Vec<egui::Pos2> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let y = rect.bottom() - (v / top).clamp(0.0, 1.0) * rect.height();
+            egui::pos2(rect.left() + i as f32 * step, y)
+        })
+        .collect();
+
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.5, ui.visuals().selection.bg_fill),
+    ));
 }
 
 fn set_styles(ctx: &Context) {
@@ -553,16 +1072,44 @@ fn set_styles(ctx: &Context) {
 fn main() -> Result<(), eframe::Error> {
     // let mut moni: Monitor = Monitor::new();
 
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let config = Config::load();
+
+    // `--backend` comes before any subcommand and overrides the `backend` config key.
+    let mut backend = config.get("backend").and_then(Backend::from_key).unwrap_or(Backend::Sysinfo);
+    if args.first().is_some_and(|a| a == "--backend") {
+        match args.get(1).and_then(|b| Backend::from_key(b)) {
+            Some(chosen) => backend = chosen,
+            None => {
+                eprintln!("--backend expects sysinfo or proc");
+                std::process::exit(2);
+            }
+        }
+        args.drain(..2);
+    }
+
+    if let Some(code) = cli::run(&args, backend) {
+        std::process::exit(code);
+    }
+
+    set_language(
+        config
+            .get("language")
+            .and_then(Language::from_key)
+            .unwrap_or_else(Language::from_env),
+    );
+
     let options = eframe::NativeOptions {
         viewport: eframe::egui::ViewportBuilder::default()
             .with_resizable(true)
-            .with_inner_size([1000.0, 600.0]),
+            .with_inner_size([1000.0, 600.0])
+            .with_min_inner_size([200.0, 120.0]),
         ..Default::default()
     };
 
     eframe::run_native(
-        "AICI",
+        tr(TITLE),
         options,
-        Box::new(|_cc| Ok(Box::<TaskManager>::default())),
+        Box::new(move |_cc| Ok(Box::new(TaskManager::new(backend)))),
     )
 }
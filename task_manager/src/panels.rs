@@ -0,0 +1,522 @@
+use crate::backend::detector::Reason;
+use crate::backend::services::ServiceState;
+use crate::backend::threads;
+use crate::i18n::{self, number, tr, trf, LANGUAGES};
+use crate::layout::{Panel, Placement, PANELS};
+use crate::{sparkline, FilterType, TaskManager, ViewType, TITLE};
+use eframe::egui::{self, CentralPanel};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HISTORY_LEN: usize = 120;
+const MAX_EVENTS: usize = 500;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum EventKind {
+    Started,
+    Exited,
+}
+
+pub struct ProcessEvent {
+    pub time: u64,
+    pub kind: EventKind,
+    pub pid: u32,
+    pub name: String,
+}
+
+fn clock(secs: u64) -> String {
+    let day = secs % 86_400;
+    format!("{:02}:{:02}:{:02}", day / 3600, day % 3600 / 60, day % 60)
+}
+
+fn push_bounded<T>(queue: &mut VecDeque<T>, value: T, max: usize) {
+    if queue.len() == max {
+        queue.pop_front();
+    }
+    queue.push_back(value);
+}
+
+impl TaskManager {
+    // Updates the histories and the start/exit log after a new snapshot arrives.
+    pub fn record_refresh(&mut self) {
+        let mem = if self.stats.total_mem == 0 {
+            0.0
+        } else {
+            self.stats.used_mem as f32 / self.stats.total_mem as f32 * 100.0
+        };
+        push_bounded(&mut self.cpu_history, self.stats.cpu, HISTORY_LEN);
+        push_bounded(&mut self.mem_history, mem, HISTORY_LEN);
+        self.sched.stale = true;
+        self.index_processes();
+
+        let all = TaskManager::data_table_view(
+            &self.stats.processes,
+            self.criteria,
+            self.sort_type,
+            FilterType::All,
+            &self.user,
+        );
+        self.detector.update(&all, self.stats.cores);
+        // Scanning every cgroup is not free, so only while someone is looking.
+        if self.layout.is_visible(Panel::Services) {
+            self.services = self.service_scanner.scan(&all);
+        }
+        let current: HashMap<u32, String> = all.into_iter().map(|p| (p.pid, p.name.clone())).collect();
+
+        // The very first snapshot would report every process as started.
+        if !self.known.is_empty() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+
+            for (pid, name) in &current {
+                if !self.known.contains_key(pid) {
+                    let event = ProcessEvent {
+                        time: now,
+                        kind: EventKind::Started,
+                        pid: *pid,
+                        name: name.clone(),
+                    };
+                    push_bounded(&mut self.events, event, MAX_EVENTS);
+                }
+            }
+            for (pid, name) in &self.known {
+                if !current.contains_key(pid) {
+                    let event = ProcessEvent {
+                        time: now,
+                        kind: EventKind::Exited,
+                        pid: *pid,
+                        name: name.clone(),
+                    };
+                    push_bounded(&mut self.events, event, MAX_EVENTS);
+                }
+            }
+        }
+
+        self.known = current;
+
+        // Threads are only sampled while someone is looking at them.
+        self.threads = match (self.view_type, self.selected) {
+            (ViewType::Threads, Some(pid)) => self.sampler.sample(pid).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+    }
+
+    fn save_layout(&mut self) {
+        self.layout.store(&mut self.config);
+        if let Err(e) = self.config.save() {
+            eprintln!("Couldn't save layout: {e}");
+        }
+    }
+
+    pub fn tab_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut changed = false;
+
+            for panel in self.layout.with(Placement::Tab) {
+                let active = self.layout.shown().first() == Some(&panel);
+                if ui.selectable_label(active, panel.title()).clicked() {
+                    self.layout.active = panel;
+                    changed = true;
+                }
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.menu_button(tr("Language"), |ui| {
+                    for language in LANGUAGES {
+                        if ui.selectable_label(i18n::language() == language, language.name()).clicked() {
+                            self.set_language(ui.ctx(), language);
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                ui.menu_button(tr("Layout"), |ui| {
+                    egui::Grid::new("layout_menu").show(ui, |ui| {
+                        for panel in PANELS {
+                            ui.label(panel.title());
+                            for placement in [
+                                Placement::Tab,
+                                Placement::Docked,
+                                Placement::Window,
+                                Placement::Hidden,
+                            ] {
+                                if ui
+                                    .radio(self.layout.placement(panel) == placement, placement.label())
+                                    .clicked()
+                                {
+                                    self.layout.set_placement(panel, placement);
+                                    changed = true;
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+            if changed {
+                self.save_layout();
+            }
+        });
+    }
+
+    fn set_language(&mut self, ctx: &egui::Context, language: i18n::Language) {
+        i18n::set_language(language);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(tr(TITLE).to_string()));
+
+        self.config.set("language", language.key());
+        if let Err(e) = self.config.save() {
+            eprintln!("Couldn't save language: {e}");
+        }
+    }
+
+    pub fn draw_panel(&mut self, ui: &mut egui::Ui, panel: Panel) {
+        match panel {
+            Panel::Processes => self.processes_panel(ui),
+            Panel::Performance => self.performance_panel(ui),
+            Panel::Users => self.users_panel(ui),
+            Panel::Events => self.events_panel(ui),
+            Panel::Details => self.details_panel(ui),
+            Panel::Suspicious => self.suspicious_panel(ui),
+            Panel::Services => self.services_panel(ui),
+        }
+    }
+
+    pub fn popped_out_windows(&mut self, ctx: &egui::Context) {
+        for panel in self.layout.with(Placement::Window) {
+            let mut closed = false;
+
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of(panel.key()),
+                egui::ViewportBuilder::default()
+                    .with_title(panel.title())
+                    .with_inner_size([700.0, 450.0]),
+                |ctx, _class| {
+                    CentralPanel::default().show(ctx, |ui| self.draw_panel(ui, panel));
+                    if ctx.input(|i| i.viewport().close_requested()) {
+                        closed = true;
+                    }
+                },
+            );
+
+            // Closing a popped out window docks the panel back as a tab.
+            if closed {
+                self.layout.set_placement(panel, Placement::Tab);
+                self.save_layout();
+            }
+        }
+    }
+
+    fn performance_panel(&mut self, ui: &mut egui::Ui) {
+        let stats = &self.stats;
+        let fmt = &self.format;
+        let width = ui.available_width().max(120.0);
+
+        ui.heading(tr("CPU"));
+        ui.label(trf(
+            "{} across {} cores",
+            &[&fmt.cpu(stats.cpu * stats.cores as f32, stats.cores), &stats.cores],
+        ));
+        let cpu: Vec<f32> = self.cpu_history.iter().copied().collect();
+        sparkline(ui, &cpu, 100.0, egui::vec2(width, 80.0));
+
+        ui.separator();
+        ui.heading(tr("Memory"));
+        ui.label(trf(
+            "{} used of {}",
+            &[
+                &crate::format::scale_bytes(stats.used_mem, fmt.units, fmt.mem_precision),
+                &crate::format::scale_bytes(stats.total_mem, fmt.units, fmt.mem_precision),
+            ],
+        ));
+        let mem: Vec<f32> = self.mem_history.iter().copied().collect();
+        sparkline(ui, &mem, 100.0, egui::vec2(width, 80.0));
+    }
+
+    fn users_panel(&mut self, ui: &mut egui::Ui) {
+        let stats = &self.stats;
+        let fmt = &self.format;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("users").num_columns(4).striped(true).show(ui, |ui| {
+                ui.strong(tr("User"));
+                ui.strong(tr("Processes"));
+                ui.strong(tr("CPU"));
+                ui.strong(tr("Memory"));
+                ui.end_row();
+
+                for totals in &self.view.users {
+                    ui.label(&totals.user);
+                    ui.label(totals.count.to_string());
+                    ui.label(fmt.cpu(totals.cpu, stats.cores));
+                    ui.label(fmt.memory(totals.memory, stats.total_mem));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn events_panel(&mut self, ui: &mut egui::Ui) {
+        if ui.button(tr("Clear")).clicked() {
+            self.events.clear();
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("events").num_columns(4).striped(true).show(ui, |ui| {
+                ui.strong(tr("Time (UTC)"));
+                ui.strong(tr("Event"));
+                ui.strong(tr("PID"));
+                ui.strong(tr("Name"));
+                ui.end_row();
+
+                for event in self.events.iter().rev() {
+                    ui.label(clock(event.time));
+                    match event.kind {
+                        EventKind::Started => ui.colored_label(egui::Color32::GREEN, tr("started")),
+                        EventKind::Exited => ui.colored_label(egui::Color32::RED, tr("exited")),
+                    };
+                    ui.label(event.pid.to_string());
+                    ui.label(&event.name);
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn details_panel(&mut self, ui: &mut egui::Ui) {
+        let stats = &self.stats;
+        let fmt = &self.format;
+
+        let Some(pid) = self.selected else {
+            ui.label(tr("Select a process in the Processes panel to see its details."));
+            return;
+        };
+
+        let Some(process) = self.process(pid) else {
+            ui.label(trf("Process {} is no longer running.", &[&pid]));
+            return;
+        };
+
+        egui::Grid::new("details").num_columns(2).striped(true).show(ui, |ui| {
+            ui.strong(tr("Name"));
+            ui.label(&process.name);
+            ui.end_row();
+            ui.strong(tr("PID"));
+            ui.label(process.pid.to_string());
+            ui.end_row();
+            ui.strong(tr("Parent PID"));
+            ui.label(process.parent_pid.to_string());
+            ui.end_row();
+            ui.strong(tr("User"));
+            ui.label(&process.user);
+            ui.end_row();
+            ui.strong(tr("Path"));
+            ui.label(&process.exe);
+            ui.end_row();
+            ui.strong(tr("CPU"));
+            ui.label(fmt.cpu(process.cpu, stats.cores));
+            ui.end_row();
+            ui.strong(tr("Memory"));
+            ui.label(fmt.memory(process.memory, stats.total_mem));
+            ui.end_row();
+            ui.strong(tr("Children"));
+            ui.label(process.child.len().to_string());
+            ui.end_row();
+
+            let Some(linux) = &process.linux else {
+                return;
+            };
+            let bytes = |value: Option<u64>| match value {
+                Some(value) => crate::format::scale_bytes(value, fmt.units, fmt.mem_precision),
+                None => tr("not permitted").to_string(),
+            };
+
+            ui.strong(tr("State"));
+            ui.label(tr(threads::state_name(linux.state)));
+            ui.end_row();
+            ui.strong(tr("Threads"));
+            ui.label(linux.threads.to_string());
+            ui.end_row();
+            ui.strong(tr("Command line"));
+            ui.label(&linux.cmdline);
+            ui.end_row();
+            ui.strong(tr("Virtual memory"));
+            ui.label(crate::format::scale_bytes(linux.virtual_mem, fmt.units, fmt.mem_precision));
+            ui.end_row();
+            ui.strong(tr("Page faults"));
+            ui.label(trf("{} minor, {} major", &[&linux.minor_faults, &linux.major_faults]));
+            ui.end_row();
+            ui.strong(tr("Disk read"));
+            ui.label(bytes(linux.read_bytes));
+            ui.end_row();
+            ui.strong(tr("Disk written"));
+            ui.label(bytes(linux.write_bytes));
+            ui.end_row();
+        });
+
+        self.scheduling_section(ui, pid);
+    }
+
+    fn suspicious_panel(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.detector.settings;
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.window)
+                        .range(2..=3600)
+                        .prefix(tr("Window: "))
+                        .suffix(tr(" samples")),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.cpu_threshold)
+                        .range(1.0..=100.0)
+                        .speed(0.5)
+                        .prefix(tr("CPU above: "))
+                        .suffix("%"),
+                )
+                .changed();
+        });
+
+        if changed {
+            self.config.set("detector.window", &settings.window.to_string());
+            self.config.set("detector.cpu_threshold", &settings.cpu_threshold.to_string());
+            if let Err(e) = self.config.save() {
+                eprintln!("Couldn't save detector settings: {e}");
+            }
+        }
+
+        ui.label(trf(
+            "Memory growing on every one of the last {} samples, or CPU above {}% for all of them.",
+            &[&settings.window, &number(settings.cpu_threshold as f64, 1)],
+        ));
+        ui.separator();
+
+        let suspects = self.detector.suspects();
+        if suspects.is_empty() {
+            ui.label(tr("Nothing suspicious so far."));
+            return;
+        }
+
+        let fmt = &self.format;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("suspects").num_columns(6).striped(true).show(ui, |ui| {
+                ui.strong(tr("PID"));
+                ui.strong(tr("Name"));
+                ui.strong(tr("Reason"));
+                ui.strong(tr("Growth"));
+                ui.strong(tr("Avg CPU"));
+                ui.strong(tr("Trend"));
+                ui.end_row();
+
+                for suspect in suspects {
+                    ui.label(suspect.pid.to_string());
+                    ui.label(&suspect.name);
+                    ui.label(match suspect.reason {
+                        Reason::Leak => tr("memory growth"),
+                        Reason::Runaway => tr("high CPU"),
+                        Reason::Both => tr("memory and CPU"),
+                    });
+                    ui.label(format!("{}/s", fmt.memory_delta(suspect.growth_per_sec as i64)));
+                    ui.label(format!("{}%", number(suspect.cpu_avg as f64, 1)));
+
+                    if suspect.reason == Reason::Runaway {
+                        sparkline(ui, &suspect.cpu, 100.0, egui::vec2(160.0, 28.0));
+                    } else {
+                        let low = *suspect.memory.first().unwrap_or(&0);
+                        let values: Vec<f32> = suspect.memory.iter().map(|m| (m - low) as f32).collect();
+                        let top = values.last().copied().unwrap_or(0.0);
+                        sparkline(ui, &values, top, egui::vec2(160.0, 28.0));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    // Selects `pid` in the tree view with all of its ancestors expanded.
+    fn show_in_tree(&mut self, pid: u32) {
+        let mut current = pid;
+        while let Some(parent) = self.process(current).map(|p| p.parent_pid) {
+            if parent == 0 || !self.open.insert(parent) {
+                break;
+            }
+            current = parent;
+        }
+
+        self.selected = Some(pid);
+        self.view_type = ViewType::Tree;
+        self.filter = FilterType::All;
+        self.view.dirty = true;
+        if self.layout.placement(Panel::Processes) == Placement::Tab {
+            self.layout.active = Panel::Processes;
+        }
+    }
+
+    fn services_panel(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.running_only, tr("Running units only"));
+
+        if self.services.is_empty() {
+            ui.label(tr("No systemd units found on this machine."));
+            return;
+        }
+
+        let fmt = &self.format;
+        let stats = &self.stats;
+        let mut jump = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("services").num_columns(8).striped(true).show(ui, |ui| {
+                ui.strong(tr("Unit"));
+                ui.strong(tr("State"));
+                ui.strong(tr("Enabled"));
+                ui.strong(tr("Main PID"));
+                ui.strong(tr("Processes"));
+                ui.strong(tr("CPU"));
+                ui.strong(tr("Memory"));
+                ui.strong(tr("Description"));
+                ui.end_row();
+
+                for service in &self.services {
+                    if self.running_only && service.state != ServiceState::Running {
+                        continue;
+                    }
+
+                    let unit = ui.label(&service.name);
+                    if let Some(path) = &service.unit_file {
+                        unit.on_hover_text(path.display().to_string());
+                    }
+                    match service.state {
+                        ServiceState::Running => ui.colored_label(egui::Color32::GREEN, tr("running")),
+                        ServiceState::Inactive => ui.label(tr("inactive")),
+                    };
+                    ui.label(if service.enabled { tr("yes") } else { tr("no") });
+                    match service.main_pid {
+                        Some(pid) => {
+                            if ui.link(pid.to_string()).on_hover_text(tr("Show in process tree")).clicked() {
+                                jump = Some(pid);
+                            }
+                        }
+                        None => {
+                            ui.label("-");
+                        }
+                    }
+                    ui.label(service.pids.len().to_string());
+                    ui.label(fmt.cpu(service.cpu, stats.cores));
+                    ui.label(fmt.memory(service.memory, stats.total_mem));
+                    ui.label(service.description.as_deref().unwrap_or(""));
+                    ui.end_row();
+                }
+            });
+        });
+
+        if let Some(pid) = jump {
+            self.show_in_tree(pid);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::backend::gatherer::ProcessInfo;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Field {
+    Pid,
+    ParentPid,
+    Name,
+    Cpu,
+    Memory,
+    Exe,
+    User,
+}
+
+pub const FIELDS: [Field; 7] = [
+    Field::Pid,
+    Field::ParentPid,
+    Field::Name,
+    Field::Cpu,
+    Field::Memory,
+    Field::Exe,
+    Field::User,
+];
+
+#[derive(PartialEq, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Contains,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+struct Term {
+    field: Field,
+    op: Op,
+    value: String,
+    number: Option<f64>,
+}
+
+// A conjunction of `field op value` terms, e.g. `user=root && cpu>5 && memory>=100M`.
+pub struct Expr {
+    terms: Vec<Term>,
+}
+
+impl Field {
+    pub fn key(self) -> &'static str {
+        match self {
+            Field::Pid => "pid",
+            Field::ParentPid => "ppid",
+            Field::Name => "name",
+            Field::Cpu => "cpu",
+            Field::Memory => "memory",
+            Field::Exe => "exe",
+            Field::User => "user",
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Field> {
+        let key = key.trim().to_lowercase();
+        match key.as_str() {
+            "mem" | "rss" => Some(Field::Memory),
+            "path" => Some(Field::Exe),
+            _ => FIELDS.into_iter().find(|f| f.key() == key),
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Field::Pid | Field::ParentPid | Field::Cpu | Field::Memory)
+    }
+}
+
+// Sizes accept K, M, G and T suffixes in binary units, `100M` is 100 MiB.
+fn parse_number(field: Field, value: &str) -> Option<f64> {
+    if field != Field::Memory {
+        return value.trim_end_matches('%').parse::<f64>().ok();
+    }
+
+    let value = value.trim_end_matches(['B', 'b']).trim_end_matches(['i']);
+    let (digits, scale) = match value.chars().last()? {
+        'K' | 'k' => (&value[..value.len() - 1], 1024f64),
+        'M' | 'm' => (&value[..value.len() - 1], 1024f64.powi(2)),
+        'G' | 'g' => (&value[..value.len() - 1], 1024f64.powi(3)),
+        'T' | 't' => (&value[..value.len() - 1], 1024f64.powi(4)),
+        _ => (value, 1.0),
+    };
+
+    digits.parse::<f64>().ok().map(|n| n * scale)
+}
+
+fn parse_term(text: &str) -> Result<Term, String> {
+    // Longest operators first so `>=` is not read as `>` followed by `=value`.
+    let ops = [
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        ("!=", Op::Ne),
+        ("==", Op::Eq),
+        ("=", Op::Eq),
+        ("~", Op::Contains),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+    ];
+
+    let Some((index, symbol, op)) = ops
+        .iter()
+        .filter_map(|(symbol, op)| text.find(symbol).map(|i| (i, *symbol, *op)))
+        .min_by_key(|(i, symbol, _)| (*i, usize::MAX - symbol.len()))
+    else {
+        return Err(format!("'{}' has no operator (=, !=, ~, >, >=, <, <=)", text));
+    };
+
+    let field = Field::parse(&text[..index]).ok_or_else(|| format!("unknown field in '{}'", text))?;
+    let value = text[index + symbol.len()..].trim().to_string();
+
+    let number = if field.is_numeric() {
+        match parse_number(field, &value) {
+            Some(n) => Some(n),
+            None if op == Op::Contains => None,
+            None => return Err(format!("'{}' is not a number", value)),
+        }
+    } else {
+        None
+    };
+
+    if !field.is_numeric() && matches!(op, Op::Gt | Op::Ge | Op::Lt | Op::Le) {
+        return Err(format!("'{}' can only be compared with =, != or ~", field.key()));
+    }
+
+    Ok(Term {
+        field,
+        op,
+        value,
+        number,
+    })
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let terms = text
+            .split("&&")
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(parse_term)
+            .collect::<Result<Vec<Term>, String>>()?;
+
+        if terms.is_empty() {
+            return Err("empty filter expression".to_string());
+        }
+
+        Ok(Expr { terms })
+    }
+
+    // CPU is compared normalized to the whole machine, like the default GUI column.
+    pub fn matches(&self, process: &ProcessInfo, cores: usize) -> bool {
+        self.terms.iter().all(|term| {
+            let text = match term.field {
+                Field::Name => process.name.as_str(),
+                Field::Exe => process.exe.as_str(),
+                Field::User => process.user.as_str(),
+                _ => "",
+            };
+            let number = match term.field {
+                Field::Pid => process.pid as f64,
+                Field::ParentPid => process.parent_pid as f64,
+                Field::Cpu => (process.cpu / cores.max(1) as f32) as f64,
+                Field::Memory => process.memory as f64,
+                _ => 0.0,
+            };
+
+            match (term.number, term.op) {
+                (_, Op::Contains) if !term.field.is_numeric() => text.contains(&term.value),
+                (_, Op::Contains) => number.to_string().contains(&term.value),
+                (Some(n), Op::Eq) => number == n,
+                (Some(n), Op::Ne) => number != n,
+                (Some(n), Op::Gt) => number > n,
+                (Some(n), Op::Ge) => number >= n,
+                (Some(n), Op::Lt) => number < n,
+                (Some(n), Op::Le) => number <= n,
+                (None, Op::Eq) => text == term.value,
+                (None, Op::Ne) => text != term.value,
+                (None, _) => false,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fields() {
+        let cases = [
+            ("pid", Some(Field::Pid)),
+            (" PPID ", Some(Field::ParentPid)),
+            ("mem", Some(Field::Memory)),
+            ("rss", Some(Field::Memory)),
+            ("path", Some(Field::Exe)),
+            ("User", Some(Field::User)),
+            ("", None),
+            ("threads", None),
+        ];
+        for (key, expected) in cases {
+            assert!(Field::parse(key) == expected, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn parses_numbers() {
+        let mib = 1024.0 * 1024.0;
+        let cases = [
+            (Field::Memory, "512", Some(512.0)),
+            (Field::Memory, "4k", Some(4096.0)),
+            (Field::Memory, "100M", Some(100.0 * mib)),
+            (Field::Memory, "100MiB", Some(100.0 * mib)),
+            (Field::Memory, "100MB", Some(100.0 * mib)),
+            (Field::Memory, "1.5G", Some(1.5 * 1024.0 * mib)),
+            (Field::Memory, "2T", Some(2.0 * 1024.0 * 1024.0 * mib)),
+            (Field::Memory, "", None),
+            (Field::Memory, "M", None),
+            (Field::Memory, "lots", None),
+            (Field::Cpu, "5", Some(5.0)),
+            (Field::Cpu, "12.5%", Some(12.5)),
+            (Field::Cpu, "5M", None),
+            (Field::Pid, "1", Some(1.0)),
+        ];
+        for (field, value, expected) in cases {
+            assert_eq!(parse_number(field, value), expected, "{} {:?}", field.key(), value);
+        }
+    }
+
+    #[test]
+    fn parses_terms() {
+        let cases = [
+            ("cpu>5", Field::Cpu, Op::Gt, "5"),
+            ("cpu>=5", Field::Cpu, Op::Ge, "5"),
+            ("memory<=1G", Field::Memory, Op::Le, "1G"),
+            ("pid!=1", Field::Pid, Op::Ne, "1"),
+            ("user==root", Field::User, Op::Eq, "root"),
+            (" name ~ fire ", Field::Name, Op::Contains, "fire"),
+            // The first operator wins, the rest belongs to the value.
+            ("name=a>b", Field::Name, Op::Eq, "a>b"),
+            ("exe~/usr/bin/a=b", Field::Exe, Op::Contains, "/usr/bin/a=b"),
+            ("pid~12", Field::Pid, Op::Contains, "12"),
+        ];
+        for (text, field, op, value) in cases {
+            let term = parse_term(text).unwrap_or_else(|e| panic!("{:?}: {}", text, e));
+            assert!(term.field == field && term.op == op, "{:?}", text);
+            assert_eq!(term.value, value, "{:?}", text);
+        }
+
+        let errors = [
+            ("cpu", "'cpu' has no operator (=, !=, ~, >, >=, <, <=)"),
+            ("cpu>", "'' is not a number"),
+            ("memory>=lots", "'lots' is not a number"),
+            ("threads>1", "unknown field in 'threads>1'"),
+            ("name>a", "'name' can only be compared with =, != or ~"),
+        ];
+        for (text, error) in errors {
+            assert_eq!(parse_term(text).err().as_deref(), Some(error), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_expressions() {
+        let expr = Expr::parse("user=root && cpu>5 &&  && memory>=100MiB").unwrap();
+        let fields: Vec<&str> = expr.terms.iter().map(|t| t.field.key()).collect();
+        assert_eq!(fields, ["user", "cpu", "memory"]);
+        assert_eq!(expr.terms[2].number, Some(100.0 * 1024.0 * 1024.0));
+
+        assert_eq!(Expr::parse("").err().as_deref(), Some("empty filter expression"));
+        assert_eq!(Expr::parse(" && ").err().as_deref(), Some("empty filter expression"));
+        assert_eq!(Expr::parse("user=root && cpu>").err().as_deref(), Some("'' is not a number"));
+    }
+
+    #[test]
+    fn matches_processes() {
+        let process = ProcessInfo {
+            pid: 42,
+            parent_pid: 1,
+            name: "firefox".to_string(),
+            cpu: 80.0,
+            memory: 200 * 1024 * 1024,
+            exe: "/usr/bin/firefox".to_string(),
+            user: "alice".to_string(),
+            child: Vec::new(),
+            linux: None,
+        };
+
+        let cases = [
+            ("user=alice && cpu>10", true),
+            // CPU is per machine, 80% of one core out of four is 20%.
+            ("cpu>25", false),
+            ("memory>=200M && memory<201M", true),
+            ("name~fox", true),
+            ("exe!=/usr/bin/firefox", false),
+            ("pid~4", true),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(Expr::parse(text).unwrap().matches(&process, 4), expected, "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::backend::gatherer::ProcessInfo;
+use crate::{FilterType, SortCriteria, SortType, TaskManager, ViewType};
+use eframe::egui;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+const FRAME_SAMPLES: usize = 120;
+
+#[derive(PartialEq, Clone, Copy)]
+struct ViewKey {
+    criteria: SortCriteria,
+    sort_type: SortType,
+    filter: FilterType,
+    tree: bool,
+}
+
+// An owned copy of what a table row shows, so drawing never touches the process tree.
+pub struct Row {
+    pub pid: u32,
+    pub name: String,
+    pub cpu: f32,
+    pub memory: u64,
+    pub exe: String,
+    pub user: String,
+    pub depth: u8,
+    pub has_children: bool,
+}
+
+pub struct UserTotals {
+    pub user: String,
+    pub count: usize,
+    pub cpu: f32,
+    pub memory: u64,
+}
+
+// Rows are rebuilt only when new data arrives, a sort/filter setting changes or a
+// tree node is toggled, instead of on every repaint.
+pub struct ViewCache {
+    key: Option<ViewKey>,
+    pub rows: Vec<Row>,
+    // Rebuilt with every snapshot, for the panels that don't depend on the sort or filter.
+    pub users: Vec<UserTotals>,
+    // Where each pid sits in the process tree, one child index per level.
+    paths: HashMap<u32, Vec<usize>>,
+    pub dirty: bool,
+    pub build_time: Duration,
+    pub frame_times: VecDeque<Duration>,
+    pub show_metrics: bool,
+}
+
+impl Default for ViewCache {
+    fn default() -> Self {
+        Self {
+            key: None,
+            rows: Vec::new(),
+            users: Vec::new(),
+            paths: HashMap::new(),
+            dirty: true,
+            build_time: Duration::ZERO,
+            frame_times: VecDeque::with_capacity(FRAME_SAMPLES),
+            show_metrics: false,
+        }
+    }
+}
+
+impl ViewCache {
+    pub fn record_frame(&mut self, time: Duration) {
+        if self.frame_times.len() == FRAME_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(time);
+    }
+}
+
+fn index_tree(processes: &[ProcessInfo], path: &mut Vec<usize>, paths: &mut HashMap<u32, Vec<usize>>) {
+    for (i, process) in processes.iter().enumerate() {
+        path.push(i);
+        paths.insert(process.pid, path.clone());
+        index_tree(&process.child, path, paths);
+        path.pop();
+    }
+}
+
+impl TaskManager {
+    pub fn index_processes(&mut self) {
+        let mut paths = HashMap::new();
+        index_tree(&self.stats.processes, &mut Vec::new(), &mut paths);
+
+        let mut per_user: BTreeMap<&str, UserTotals> = BTreeMap::new();
+        for path in paths.values() {
+            let Some(process) = self.process_at(path) else {
+                continue;
+            };
+            let entry = per_user.entry(process.user.as_str()).or_insert_with(|| UserTotals {
+                user: process.user.clone(),
+                count: 0,
+                cpu: 0.0,
+                memory: 0,
+            });
+            entry.count += 1;
+            entry.cpu += process.cpu;
+            entry.memory += process.memory;
+        }
+
+        let mut users: Vec<UserTotals> = per_user.into_values().collect();
+        users.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+
+        self.view.users = users;
+        self.view.paths = paths;
+    }
+
+    fn process_at(&self, path: &[usize]) -> Option<&ProcessInfo> {
+        let (first, rest) = path.split_first()?;
+        let mut process = self.stats.processes.get(*first)?;
+        for i in rest {
+            process = process.child.get(*i)?;
+        }
+        Some(process)
+    }
+
+    pub fn process(&self, pid: u32) -> Option<&ProcessInfo> {
+        self.process_at(self.view.paths.get(&pid)?)
+    }
+
+    pub fn refresh_view(&mut self) {
+        let key = ViewKey {
+            criteria: self.criteria,
+            sort_type: self.sort_type,
+            filter: self.filter,
+            // The compact widget always ranks the flat list.
+            tree: self.view_type == ViewType::Tree && !self.compact,
+        };
+
+        if !self.view.dirty && self.view.key == Some(key) {
+            return;
+        }
+
+        let started = Instant::now();
+        let processes = &self.stats.processes;
+
+        let entries = if key.tree {
+            TaskManager::data_tree_view(
+                processes,
+                key.criteria,
+                key.sort_type,
+                key.filter,
+                &self.user,
+                &self.open,
+            )
+        } else {
+            TaskManager::data_table_view(processes, key.criteria, key.sort_type, key.filter, &self.user)
+                .into_iter()
+                .map(|p| (p, 0))
+                .collect()
+        };
+
+        self.view.rows = entries
+            .into_iter()
+            .map(|(process, depth)| Row {
+                pid: process.pid,
+                name: process.name.clone(),
+                cpu: process.cpu,
+                memory: process.memory,
+                exe: process.exe.clone(),
+                user: process.user.clone(),
+                depth,
+                has_children: !process.child.is_empty(),
+            })
+            .collect();
+
+        self.view.key = Some(key);
+        self.view.dirty = false;
+        self.view.build_time = started.elapsed();
+    }
+
+    pub fn metrics_overlay(&self, ctx: &egui::Context) {
+        let frames = &self.view.frame_times;
+        let total: Duration = frames.iter().sum();
+        let average = total.as_secs_f64() * 1000.0 / frames.len().max(1) as f64;
+        let worst = frames.iter().max().copied().unwrap_or_default();
+
+        egui::Area::new(egui::Id::new("frame_metrics"))
+            .anchor(egui::Align2::RIGHT_TOP, [-8.0, 32.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.monospace(format!("frame avg  {:>7.2} ms", average));
+                    ui.monospace(format!("frame max  {:>7.2} ms", worst.as_secs_f64() * 1000.0));
+                    ui.monospace(format!("view build {:>7.2} ms", self.view.build_time.as_secs_f64() * 1000.0));
+                    ui.monospace(format!("rows       {:>7}", self.view.rows.len()));
+                });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::gatherer::{build_tree, Backend};
+    use crate::SortType;
+
+    const PROCESSES: u32 = 5000;
+
+    // Every pid hangs below pid / 4, which gives a bushy tree about six levels deep.
+    fn synthetic_tree() -> Vec<ProcessInfo> {
+        let mut families: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
+        for pid in 1..=PROCESSES {
+            let parent_pid = pid / 4;
+            families.entry(parent_pid).or_default().push(ProcessInfo {
+                pid,
+                parent_pid,
+                name: format!("p{}", pid),
+                cpu: (pid % 97) as f32,
+                memory: pid as u64 * 4096,
+                exe: String::new(),
+                user: "alice".to_string(),
+                child: Vec::new(),
+                linux: None,
+            });
+        }
+        build_tree(families)
+    }
+
+    #[test]
+    fn reuses_rows_until_the_view_changes() {
+        let mut manager = TaskManager::new(Backend::Sysinfo);
+        manager.stats.processes = synthetic_tree();
+        manager.filter = FilterType::All;
+        manager.view_type = ViewType::Tree;
+        manager.open = (1..=PROCESSES).collect();
+        manager.index_processes();
+        assert_eq!(manager.process(PROCESSES).map(|p| p.parent_pid), Some(PROCESSES / 4));
+
+        manager.refresh_view();
+        assert_eq!(manager.view.rows.len(), PROCESSES as usize);
+
+        // Clearing the rows behind the cache's back shows whether a refresh rebuilt them.
+        manager.view.rows.clear();
+        manager.refresh_view();
+        manager.refresh_view();
+        assert!(manager.view.rows.is_empty());
+
+        manager.sort_type = SortType::Ascending;
+        manager.refresh_view();
+        assert_eq!(manager.view.rows.len(), PROCESSES as usize);
+
+        manager.view.rows.clear();
+        manager.compact = true;
+        manager.refresh_view();
+        assert_eq!(manager.view.rows.len(), PROCESSES as usize);
+        assert!(manager.view.rows.iter().all(|row| row.depth == 0));
+
+        // A new sample marks the cache dirty without changing the key.
+        manager.view.rows.clear();
+        manager.view.dirty = true;
+        manager.refresh_view();
+        assert_eq!(manager.view.rows.len(), PROCESSES as usize);
+    }
+}